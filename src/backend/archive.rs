@@ -0,0 +1,281 @@
+use crate::backend::scratch_dir;
+use crate::backend::sniff::sniff_csv;
+use crate::cli::connect::{conn_parser, is_data_file_name, DataSetConn, FileOpts};
+use crate::cli::ConnectOpts;
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use glob::{MatchOptions, Pattern};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The members of a zip or tar archive matching `--member`, every data file if omitted,
+/// extracted to the scratch directory of the session and connected like files of their own.
+/// The name of each connection is the member file name without its extensions, or its whole
+/// path if file names are ambiguous
+pub fn archive_members(opts: &ConnectOpts, archive: &FileOpts) -> anyhow::Result<Vec<ConnectOpts>> {
+    let pattern = opts.member.as_deref().map(Pattern::new).transpose()?;
+    let is_member = |name: &str| {
+        let hidden = name
+            .split('/')
+            .any(|p| p.starts_with(['.', '_']) || p == "__MACOSX");
+        let file_name = name.rsplit('/').next().unwrap_or_default();
+        !hidden
+            && is_data_file_name(file_name)
+            && pattern.as_ref().is_none_or(|p| {
+                let options = MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                };
+                p.matches_with(name, options)
+            })
+    };
+
+    let scratch_dir = opts.scratch_dir.clone().unwrap_or_else(scratch_dir);
+    let dir = extract_dir(&scratch_dir, &archive.filename)?;
+    let mut members = match archive.ext.to_lowercase().ends_with(".zip") {
+        true => extract_zip(&archive.filename, &dir, is_member)?,
+        false => extract_tar(archive, &dir, is_member)?,
+    };
+    if members.is_empty() {
+        return Err(anyhow!(
+            "no {}members found in {}",
+            opts.member
+                .as_ref()
+                .map(|m| format!("{} ", m))
+                .unwrap_or_default(),
+            archive.filename
+        ));
+    }
+    members.sort();
+
+    let stems = members
+        .iter()
+        .map(|m| table_name(m.rsplit('/').next().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    let unique = stems.iter().collect::<HashSet<_>>().len() == stems.len();
+    members
+        .iter()
+        .zip(stems)
+        .map(|(member, stem)| {
+            let path = dir.join(member).to_string_lossy().to_string();
+            let mut member_opts = opts.clone();
+            member_opts.conn = Some(conn_parser(&path).map_err(|e| anyhow!(e))?);
+            member_opts.name = if unique { stem } else { table_name(member) };
+            if let Some(DataSetConn::Csv(file_opts)) = &member_opts.conn {
+                sniff_csv(file_opts, &mut member_opts.csv)?;
+            }
+            Ok(member_opts)
+        })
+        .collect()
+}
+
+/// Every archive has a directory of its own, named after its path, size and modification time,
+/// so members extracted by an earlier connect are those of the same archive
+fn extract_dir(scratch_dir: &Path, filename: &str) -> anyhow::Result<PathBuf> {
+    let meta = std::fs::metadata(filename)?;
+    let mut hasher = DefaultHasher::new();
+    std::fs::canonicalize(filename)?.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    meta.modified()?.hash(&mut hasher);
+    let stem = Path::new(filename)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    Ok(scratch_dir
+        .join("archives")
+        .join(format!("{}-{:016x}", stem, hasher.finish())))
+}
+
+fn extract_zip(
+    filename: &str,
+    dir: &Path,
+    is_member: impl Fn(&str) -> bool,
+) -> anyhow::Result<Vec<String>> {
+    let mut zip = zip::ZipArchive::new(File::open(filename)?)?;
+    let mut members = vec![];
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let Some(name) = entry.enclosed_name().map(|p| member_name(&p)) else {
+            continue;
+        };
+        if entry.is_file() && is_member(&name) {
+            let (size, modified) = (entry.size(), zip_modified(&entry));
+            write_member(&mut entry, &dir.join(&name), size, modified)?;
+            members.push(name);
+        }
+    }
+    Ok(members)
+}
+
+fn extract_tar(
+    archive: &FileOpts,
+    dir: &Path,
+    is_member: impl Fn(&str) -> bool,
+) -> anyhow::Result<Vec<String>> {
+    let reader = archive
+        .compression
+        .convert_read(File::open(&archive.filename)?)?;
+    let mut tar = tar::Archive::new(reader);
+    let mut members = vec![];
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        // members must stay inside the extraction directory
+        if !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            continue;
+        }
+        let name = member_name(&path);
+        if entry.header().entry_type().is_file() && is_member(&name) {
+            let size = entry.header().size()?;
+            let modified = entry.header().mtime().ok();
+            let modified = modified.map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+            write_member(&mut entry, &dir.join(&name), size, modified)?;
+            members.push(name);
+        }
+    }
+    Ok(members)
+}
+
+/// The member path with `/` separators and without a leading `./`
+fn member_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(p) => Some(p.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Members are extracted with the modification time of their entry, those already extracted
+/// with the same size and time are left as they are since tables of an earlier connect may
+/// read them. Others are written to a temporary file first so they're never read half written
+fn write_member(
+    reader: &mut impl Read,
+    path: &Path,
+    size: u64,
+    modified: Option<SystemTime>,
+) -> anyhow::Result<()> {
+    let extracted = path.metadata().ok().map(|m| (m.len(), m.modified().ok()));
+    if modified.is_some() && extracted == Some((size, modified)) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let mut file = File::create(&part)?;
+    std::io::copy(reader, &mut file)?;
+    if let Some(modified) = modified {
+        file.set_modified(modified)?;
+    }
+    std::fs::rename(part, path)?;
+    Ok(())
+}
+
+/// Zip entries are dated in local time without an offset, taken as UTC
+fn zip_modified(entry: &zip::read::ZipFile<'_>) -> Option<SystemTime> {
+    let t = entry.last_modified()?;
+    let datetime = NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?
+        .and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+    let secs = u64::try_from(datetime.and_utc().timestamp()).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// A member path as a table name, extensions dropped and anything but letters, digits and
+/// `_` replaced, e.g. `sales/2024.csv.gz` becomes `sales_2024`
+fn table_name(member: &str) -> String {
+    let (dir, file_name) = member.rsplit_once('/').unwrap_or(("", member));
+    let stem = file_name.split('.').next().unwrap_or_default();
+    let name = match dir {
+        "" => stem.to_string(),
+        dir => format!("{}/{}", dir, stem),
+    };
+    let name = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn archive(path: &Path) -> FileOpts {
+        match conn_parser(&path.to_string_lossy()).unwrap() {
+            DataSetConn::Archive(file_opts) => file_opts,
+            v => panic!("expected an archive, got {:?}", v),
+        }
+    }
+
+    fn member_path(members: &[ConnectOpts], name: &str) -> PathBuf {
+        let member = members.iter().find(|m| m.name == name).unwrap();
+        match member.conn() {
+            DataSetConn::Csv(file_opts) => PathBuf::from(&file_opts.filename),
+            v => panic!("expected a csv member, got {:?}", v),
+        }
+    }
+
+    #[test]
+    fn archive_members_should_extract_changed_members_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("sales.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let modified = zip::DateTime::from_date_and_time(2024, 5, 1, 10, 30, 0).unwrap();
+        let options = SimpleFileOptions::default().last_modified_time(modified);
+        for (name, data) in [("2024/q1.csv", "id,total\n1,10\n"), ("notes.txt", "x")] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        let mut tar = tar::Builder::new(File::create(dir.path().join("sales.tar")).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(14);
+        header.set_mtime(1_714_559_400);
+        header.set_cksum();
+        tar.append_data(&mut header, "q2.csv", "id,total\n2,20\n".as_bytes())
+            .unwrap();
+        tar.finish().unwrap();
+
+        let mut opts = ConnectOpts::new(DataSetConn::Stdin, None, "sales".to_string());
+        opts.scratch_dir = Some(dir.path().join("scratch"));
+        let members = archive_members(&opts, &archive(&zip_path)).unwrap();
+        assert_eq!(
+            members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(),
+            ["q1"]
+        );
+        let q1 = member_path(&members, "q1");
+        assert!(q1.starts_with(dir.path().join("scratch").join("archives")));
+        let modified = UNIX_EPOCH + Duration::from_secs(1_714_559_400);
+        assert_eq!(q1.metadata().unwrap().modified().unwrap(), modified);
+
+        // a member of the same size but changed since is replaced
+        std::fs::write(&q1, "id,total\n9,99\n").unwrap();
+        let members = archive_members(&opts, &archive(&zip_path)).unwrap();
+        assert_eq!(member_path(&members, "q1"), q1);
+        assert_eq!(std::fs::read_to_string(&q1).unwrap(), "id,total\n1,10\n");
+
+        let members = archive_members(&opts, &archive(&dir.path().join("sales.tar"))).unwrap();
+        let q2 = member_path(&members, "q2");
+        assert_eq!(std::fs::read_to_string(&q2).unwrap(), "id,total\n2,20\n");
+        assert_eq!(q2.metadata().unwrap().modified().unwrap(), modified);
+
+        std::fs::remove_dir_all(q1.parent().unwrap().parent().unwrap()).unwrap();
+        std::fs::remove_dir_all(q2.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,628 @@
+use crate::cli::connect::SnapshotOpts;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A snapshot of a Delta table, the data files live at a version of its transaction log.
+/// Partition columns aren't stored in the files, they come last in the schema and their
+/// values are taken from the log
+#[derive(Debug, Clone)]
+pub struct DeltaTable {
+    schema: SchemaRef,
+    partition_columns: Vec<String>,
+    files: Vec<DeltaFile>,
+}
+
+/// A parquet data file of a snapshot with the values of its partition columns
+#[derive(Debug, Clone)]
+pub struct DeltaFile {
+    pub path: String,
+    pub size: u64,
+    pub partition_values: HashMap<String, Option<String>>,
+}
+
+type Checkpoints = BTreeMap<i64, Vec<PathBuf>>;
+
+/// The state of the table while its log is replayed, later actions win
+#[derive(Debug, Default)]
+struct Replay {
+    metadata: Option<Value>,
+    files: BTreeMap<String, Value>,
+}
+
+impl DeltaTable {
+    /// The latest snapshot of the table in `location`, or the one at `--version` or the last
+    /// commit at or before `--timestamp`
+    pub fn try_new(location: &str, opts: &SnapshotOpts) -> anyhow::Result<Self> {
+        // files are scanned from the root of the local object store, their paths are absolute
+        let location = std::fs::canonicalize(location)
+            .map_err(|e| anyhow!("failed to open delta table {}: {}", location, e))?;
+        let location = &location.to_string_lossy().to_string();
+        let log = Path::new(location).join("_delta_log");
+        let (commits, checkpoints) = log_files(&log)?;
+        let latest = commits
+            .keys()
+            .chain(checkpoints.keys())
+            .max()
+            .copied()
+            .ok_or_else(|| anyhow!("no commits found in {}", log.display()))?;
+        let version = match (opts.version, opts.timestamp) {
+            (Some(version), _) if version > latest => {
+                return Err(anyhow!(
+                    "version {} not found in {}, the latest is {}",
+                    version,
+                    location,
+                    latest
+                ))
+            }
+            (Some(version), _) => version,
+            (None, Some(timestamp)) => version_at(&commits, timestamp, location)?,
+            (None, None) => latest,
+        };
+
+        // a checkpoint holds every action up to its version, commits are replayed after it
+        let checkpoint = checkpoints.range(..=version).next_back();
+        let start = checkpoint.map(|(v, _)| v + 1).unwrap_or(0);
+        let mut replay = Replay::default();
+        for part in checkpoint
+            .map(|(_, parts)| parts.as_slice())
+            .unwrap_or_default()
+        {
+            let reader = SerializedFileReader::new(File::open(part)?)?;
+            for row in reader.get_row_iter(None)? {
+                replay.apply(row?.to_json_value())?;
+            }
+        }
+        for v in start..=version {
+            let commit = commits.get(&v).ok_or_else(|| {
+                anyhow!(
+                    "version {} is missing from the log of {}, it may have been cleaned up",
+                    v,
+                    location
+                )
+            })?;
+            for line in BufReader::new(File::open(commit)?).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    replay.apply(serde_json::from_str(&line)?)?;
+                }
+            }
+        }
+        replay.into_table(location)
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// The columns stored in the data files
+    pub fn file_schema(&self) -> SchemaRef {
+        let fields = &self.schema.fields()[..self.file_columns()];
+        Arc::new(Schema::new(fields.to_vec()))
+    }
+
+    /// The columns only known from the log
+    pub fn partition_schema(&self) -> SchemaRef {
+        let fields = &self.schema.fields()[self.file_columns()..];
+        Arc::new(Schema::new(fields.to_vec()))
+    }
+
+    pub fn files(&self) -> &[DeltaFile] {
+        &self.files
+    }
+
+    fn file_columns(&self) -> usize {
+        self.schema.fields().len() - self.partition_columns.len()
+    }
+}
+
+impl DeltaFile {
+    pub fn partition_value(&self, column: &str) -> Option<&str> {
+        self.partition_values.get(column).and_then(|v| v.as_deref())
+    }
+}
+
+impl Replay {
+    fn apply(&mut self, action: Value) -> anyhow::Result<()> {
+        if let Some(protocol) = action.get("protocol").filter(|p| !p.is_null()) {
+            check_protocol(protocol)?;
+        }
+        if let Some(metadata) = action.get("metaData").filter(|m| !m.is_null()) {
+            self.metadata = Some(metadata.clone());
+        }
+        if let Some(add) = action.get("add").filter(|a| !a.is_null()) {
+            if add.get("deletionVector").is_some_and(|dv| !dv.is_null()) {
+                return Err(anyhow!("tables with deletion vectors are not supported"));
+            }
+            self.files
+                .insert(string(add, "path")?.to_string(), add.clone());
+        }
+        if let Some(remove) = action.get("remove").filter(|r| !r.is_null()) {
+            self.files.remove(string(remove, "path")?);
+        }
+        Ok(())
+    }
+
+    fn into_table(self, location: &str) -> anyhow::Result<DeltaTable> {
+        let metadata = self
+            .metadata
+            .ok_or_else(|| anyhow!("no table metadata found in the log of {}", location))?;
+        let mode = metadata
+            .pointer("/configuration/delta.columnMapping.mode")
+            .and_then(Value::as_str);
+        if mode.is_some_and(|m| m != "none") {
+            return Err(anyhow!("tables with column mapping are not supported"));
+        }
+        let partition_columns = metadata
+            .get("partitionColumns")
+            .and_then(Value::as_array)
+            .map(|cols| {
+                cols.iter()
+                    .filter_map(|c| c.as_str().map(String::from))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let schema: Value = serde_json::from_str(string(&metadata, "schemaString")?)?;
+        let (mut columns, partitions): (Vec<_>, Vec<_>) = struct_fields(&schema)?
+            .iter()
+            .cloned()
+            .partition(|f| !partition_columns.contains(f.name()));
+        // partition columns follow the file columns in the order they partition the table
+        for col in &partition_columns {
+            let field = partitions
+                .iter()
+                .find(|f| f.name() == col)
+                .ok_or_else(|| anyhow!("partition column {} not found in the schema", col))?;
+            columns.push(field.clone());
+        }
+
+        let files = self
+            .files
+            .into_iter()
+            .map(|(path, add)| {
+                let partition_values = add
+                    .get("partitionValues")
+                    .and_then(Value::as_object)
+                    .map(|values| {
+                        values
+                            .iter()
+                            // an empty value is a null partition
+                            .map(|(k, v)| {
+                                let v = v.as_str().filter(|v| !v.is_empty());
+                                (k.clone(), v.map(String::from))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let size = add.get("size").and_then(Value::as_u64).unwrap_or_default();
+                Ok(DeltaFile {
+                    path: Path::new(location)
+                        .join(percent_decode(&path)?)
+                        .to_string_lossy()
+                        .to_string(),
+                    size,
+                    partition_values,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(DeltaTable {
+            schema: Arc::new(Schema::new(columns)),
+            partition_columns,
+            files,
+        })
+    }
+}
+
+/// Commits and complete checkpoints of the log by version, a checkpoint may be split in parts
+fn log_files(log: &Path) -> anyhow::Result<(BTreeMap<i64, PathBuf>, Checkpoints)> {
+    let mut commits = BTreeMap::new();
+    let mut parts: BTreeMap<i64, (usize, Vec<PathBuf>)> = BTreeMap::new();
+    for entry in std::fs::read_dir(log)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let segments = name.split('.').collect::<Vec<_>>();
+        let Some(Ok(version)) = segments.first().map(|v| v.parse::<i64>()) else {
+            continue;
+        };
+        match &segments[1..] {
+            ["json"] => {
+                commits.insert(version, path);
+            }
+            ["checkpoint", "parquet"] => {
+                parts.insert(version, (1, vec![path]));
+            }
+            ["checkpoint", _, count, "parquet"] => {
+                let count = count.parse()?;
+                let entry = parts.entry(version).or_insert((count, vec![]));
+                entry.1.push(path);
+            }
+            _ => {}
+        }
+    }
+    let checkpoints = parts
+        .into_iter()
+        .filter(|(_, (count, paths))| paths.len() == *count)
+        .map(|(version, (_, mut paths))| {
+            paths.sort();
+            (version, paths)
+        })
+        .collect();
+    Ok((commits, checkpoints))
+}
+
+/// The last version committed at or before the timestamp. A commit is dated by its
+/// `commitInfo`, or the modification time of its file for writers that don't record it
+fn version_at(
+    commits: &BTreeMap<i64, PathBuf>,
+    timestamp: DateTime<Utc>,
+    location: &str,
+) -> anyhow::Result<i64> {
+    let mut found = None;
+    for (version, path) in commits {
+        let commit_info = BufReader::new(File::open(path)?)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+            .find_map(|action| action.get("commitInfo").cloned());
+        let millis = match commit_info.and_then(|c| c.get("timestamp").and_then(Value::as_i64)) {
+            Some(millis) => millis,
+            None => DateTime::<Utc>::from(std::fs::metadata(path)?.modified()?).timestamp_millis(),
+        };
+        if millis > timestamp.timestamp_millis() {
+            break;
+        }
+        found = Some(*version);
+    }
+    found.ok_or_else(|| anyhow!("no version of {} at or before {}", location, timestamp))
+}
+
+/// Readers of protocol 3 list the features they need, only those which don't change how
+/// files are read are supported
+fn check_protocol(protocol: &Value) -> anyhow::Result<()> {
+    let supported = ["timestampNtz", "deletionVectors", "vacuumProtocolCheck"];
+    let features = protocol
+        .get("readerFeatures")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    for feature in features {
+        if !supported.contains(&feature) {
+            return Err(anyhow!("delta reader feature {} is not supported", feature));
+        }
+    }
+    Ok(())
+}
+
+fn struct_fields(schema: &Value) -> anyhow::Result<Fields> {
+    let fields = schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("invalid delta struct type {}", schema))?;
+    fields
+        .iter()
+        .map(|f| {
+            let nullable = f.get("nullable").and_then(Value::as_bool).unwrap_or(true);
+            let data_type = delta_type(f.get("type").unwrap_or(&Value::Null))?;
+            Ok(Field::new(string(f, "name")?, data_type, nullable))
+        })
+        .collect()
+}
+
+/// The arrow type of a delta type, primitives are named and nested types are objects
+fn delta_type(t: &Value) -> anyhow::Result<DataType> {
+    if let Some(name) = t.as_str() {
+        let data_type = match name {
+            "string" => DataType::Utf8,
+            "long" => DataType::Int64,
+            "integer" => DataType::Int32,
+            "short" => DataType::Int16,
+            "byte" => DataType::Int8,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "boolean" => DataType::Boolean,
+            "binary" => DataType::Binary,
+            "date" => DataType::Date32,
+            "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            "timestamp_ntz" => DataType::Timestamp(TimeUnit::Microsecond, None),
+            decimal if decimal.starts_with("decimal(") => {
+                let (precision, scale) = decimal["decimal(".len()..]
+                    .trim_end_matches(')')
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("invalid delta type {}", decimal))?;
+                DataType::Decimal128(precision.trim().parse()?, scale.trim().parse()?)
+            }
+            v => return Err(anyhow!("delta type {} is not supported", v)),
+        };
+        return Ok(data_type);
+    }
+
+    match t.get("type").and_then(Value::as_str) {
+        Some("struct") => Ok(DataType::Struct(struct_fields(t)?)),
+        Some("array") => {
+            let element = delta_type(t.get("elementType").unwrap_or(&Value::Null))?;
+            let nullable = t.get("containsNull").and_then(Value::as_bool);
+            let field = Field::new("element", element, nullable.unwrap_or(true));
+            Ok(DataType::List(Arc::new(field)))
+        }
+        Some("map") => {
+            let key = delta_type(t.get("keyType").unwrap_or(&Value::Null))?;
+            let value = delta_type(t.get("valueType").unwrap_or(&Value::Null))?;
+            let nullable = t.get("valueContainsNull").and_then(Value::as_bool);
+            let entries = Fields::from(vec![
+                Field::new("key", key, false),
+                Field::new("value", value, nullable.unwrap_or(true)),
+            ]);
+            let field = Field::new("key_value", DataType::Struct(entries), false);
+            Ok(DataType::Map(Arc::new(field), false))
+        }
+        _ => Err(anyhow!("invalid delta type {}", t)),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> anyhow::Result<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("{} not found in delta action {}", key, value))
+}
+
+/// Paths of the log are relative uris, e.g. `date=2024-01-01%2010%3A00/part-0.parquet`
+fn percent_decode(path: &str) -> anyhow::Result<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3])?;
+                decoded.push(u8::from_str_radix(hex, 16)?);
+                i += 3;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    Ok(String::from_utf8(decoded)?)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use datafusion::arrow::array::RecordBatch;
+    use datafusion::arrow::json::ReaderBuilder;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use serde_json::json;
+
+    const SCHEMA: &str = r#"{"type":"struct","fields":[
+        {"name":"id","type":"long","nullable":true,"metadata":{}},
+        {"name":"day","type":"string","nullable":true,"metadata":{}}
+    ]}"#;
+
+    fn protocol() -> Value {
+        json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}})
+    }
+
+    fn metadata() -> Value {
+        json!({"metaData": {"id": "events", "schemaString": SCHEMA, "partitionColumns": ["day"]}})
+    }
+
+    /// Add a data file written by `events`, with its size
+    fn add(dir: &Path, path: &str, day: &str) -> Value {
+        let size = std::fs::metadata(dir.join(path)).unwrap().len();
+        json!({"add": {"path": path, "size": size, "partitionValues": {"day": day}, "dataChange": true}})
+    }
+
+    fn write_parquet(path: &Path, ids: &[i64]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let ids = Arc::new(datafusion::arrow::array::Int64Array::from(ids.to_vec()));
+        let batch = RecordBatch::try_new(schema.clone(), vec![ids]).unwrap();
+        let mut writer = ArrowWriter::try_new(File::create(path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    /// A table partitioned by day, version 0 adds a file to day a, version 1 one to day b
+    /// and version 2 replaces the file of day a. Commits are a second apart from 1970
+    pub(crate) fn events(dir: &Path) {
+        write_parquet(&dir.join("day=a/part-0.parquet"), &[1, 2]);
+        write_parquet(&dir.join("day=b/part-1.parquet"), &[3]);
+        write_parquet(&dir.join("day=a/part-2.parquet"), &[10]);
+        let log = dir.join("_delta_log");
+        std::fs::create_dir_all(&log).unwrap();
+        let commits = [
+            vec![
+                protocol(),
+                metadata(),
+                add(dir, "day=a/part-0.parquet", "a"),
+            ],
+            vec![add(dir, "day=b/part-1.parquet", "b")],
+            vec![
+                json!({"remove": {"path": "day=a/part-0.parquet"}}),
+                add(dir, "day=a/part-2.parquet", "a"),
+            ],
+        ];
+        for (version, actions) in commits.into_iter().enumerate() {
+            let commit_info = json!({"commitInfo": {"timestamp": (version + 1) * 1000}});
+            let lines = actions
+                .iter()
+                .chain([&commit_info])
+                .map(Value::to_string)
+                .collect::<Vec<_>>();
+            let commit = log.join(format!("{:020}.json", version));
+            std::fs::write(commit, lines.join("\n")).unwrap();
+        }
+    }
+
+    /// A checkpoint of version 1, the commits it holds are removed like a log cleanup does
+    fn checkpoint(dir: &Path) {
+        let fields = |fields: Vec<Field>| DataType::Struct(fields.into());
+        let string = |name| Field::new(name, DataType::Utf8, true);
+        let entries = fields(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, true),
+        ]);
+        let partition_values =
+            DataType::Map(Arc::new(Field::new("key_value", entries, false)), false);
+        let partition_columns = DataType::List(Arc::new(string("element")));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "protocol",
+                fields(vec![
+                    Field::new("minReaderVersion", DataType::Int32, true),
+                    Field::new("minWriterVersion", DataType::Int32, true),
+                ]),
+                true,
+            ),
+            Field::new(
+                "metaData",
+                fields(vec![
+                    string("id"),
+                    string("schemaString"),
+                    Field::new("partitionColumns", partition_columns, true),
+                ]),
+                true,
+            ),
+            Field::new(
+                "add",
+                fields(vec![
+                    string("path"),
+                    Field::new("size", DataType::Int64, true),
+                    Field::new("partitionValues", partition_values, true),
+                    Field::new("dataChange", DataType::Boolean, true),
+                ]),
+                true,
+            ),
+        ]));
+        let actions = [
+            protocol(),
+            metadata(),
+            add(dir, "day=a/part-0.parquet", "a"),
+            add(dir, "day=b/part-1.parquet", "b"),
+        ];
+        let mut decoder = ReaderBuilder::new(schema.clone()).build_decoder().unwrap();
+        decoder.serialize(&actions).unwrap();
+        let batch = decoder.flush().unwrap().unwrap();
+
+        let log = dir.join("_delta_log");
+        let path = log.join(format!("{:020}.checkpoint.parquet", 1));
+        let mut writer = ArrowWriter::try_new(File::create(path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        for version in 0..=1 {
+            std::fs::remove_file(log.join(format!("{:020}.json", version))).unwrap();
+        }
+    }
+
+    /// The path of `dir` relative to the working directory
+    pub(crate) fn relative(dir: &Path) -> String {
+        let cwd = std::env::current_dir().unwrap();
+        let up = cwd.components().count() - 1;
+        let mut path = std::iter::repeat_n("..", up).collect::<PathBuf>();
+        path.push(dir.strip_prefix("/").unwrap());
+        path.to_string_lossy().to_string()
+    }
+
+    /// The files of the table by name with their day
+    fn files(table: &DeltaTable) -> Vec<(String, Option<&str>)> {
+        table
+            .files()
+            .iter()
+            .map(|file| {
+                let name = Path::new(&file.path).file_name().unwrap();
+                (
+                    name.to_string_lossy().to_string(),
+                    file.partition_value("day"),
+                )
+            })
+            .collect()
+    }
+
+    fn snapshot(version: Option<i64>, timestamp: Option<&str>) -> SnapshotOpts {
+        SnapshotOpts {
+            version,
+            snapshot_id: None,
+            timestamp: timestamp.map(|t| t.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn delta_table_should_replay_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        events(dir.path());
+        let location = relative(dir.path());
+
+        let table = DeltaTable::try_new(&location, &snapshot(None, None)).unwrap();
+        let names = |schema: SchemaRef| {
+            let fields = schema.fields().iter();
+            fields.map(|f| f.name().clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(table.schema()), ["id", "day"]);
+        assert_eq!(names(table.file_schema()), ["id"]);
+        assert_eq!(names(table.partition_schema()), ["day"]);
+        assert_eq!(
+            files(&table),
+            [
+                ("part-2.parquet".to_string(), Some("a")),
+                ("part-1.parquet".to_string(), Some("b"))
+            ]
+        );
+        // paths are absolute whatever the location
+        let dir = dir.path().canonicalize().unwrap();
+        assert!(table
+            .files()
+            .iter()
+            .all(|f| f.path.starts_with(dir.to_str().unwrap())));
+
+        let table = DeltaTable::try_new(&location, &snapshot(Some(0), None)).unwrap();
+        assert_eq!(files(&table), [("part-0.parquet".to_string(), Some("a"))]);
+        let e = DeltaTable::try_new(&location, &snapshot(Some(3), None)).unwrap_err();
+        assert!(e.to_string().ends_with("the latest is 2"), "{}", e);
+
+        let at = snapshot(None, Some("1970-01-01T00:00:02.500Z"));
+        let table = DeltaTable::try_new(&location, &at).unwrap();
+        assert_eq!(files(&table).len(), 2);
+        assert_eq!(files(&table)[0].0, "part-0.parquet");
+        let before = snapshot(None, Some("1970-01-01T00:00:00Z"));
+        assert!(DeltaTable::try_new(&location, &before).is_err());
+    }
+
+    #[test]
+    fn delta_table_should_start_from_checkpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        events(dir.path());
+        checkpoint(dir.path());
+        let location = dir.path().to_string_lossy();
+
+        let table = DeltaTable::try_new(&location, &snapshot(None, None)).unwrap();
+        assert_eq!(
+            files(&table),
+            [
+                ("part-2.parquet".to_string(), Some("a")),
+                ("part-1.parquet".to_string(), Some("b"))
+            ]
+        );
+        let table = DeltaTable::try_new(&location, &snapshot(Some(1), None)).unwrap();
+        assert_eq!(
+            files(&table),
+            [
+                ("part-0.parquet".to_string(), Some("a")),
+                ("part-1.parquet".to_string(), Some("b"))
+            ]
+        );
+        let e = DeltaTable::try_new(&location, &snapshot(Some(0), None)).unwrap_err();
+        assert!(e.to_string().contains("cleaned up"), "{}", e);
+    }
+}
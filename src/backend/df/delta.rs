@@ -0,0 +1,130 @@
+use crate::backend::delta::DeltaTable;
+use crate::cli::connect::SnapshotOpts;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::common::{ScalarValue, ToDFSchema};
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::Result;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use std::any::Any;
+use std::sync::Arc;
+
+/// A snapshot of a Delta table, its data files are scanned by the parquet reader with the
+/// partition values recorded in the log. Filters prune row groups but are applied again
+/// on the rows read.
+#[derive(Debug)]
+pub struct DeltaProvider(DeltaTable);
+
+impl DeltaProvider {
+    pub fn try_new(location: &str, opts: &SnapshotOpts) -> anyhow::Result<Self> {
+        Ok(Self(DeltaTable::try_new(location, opts)?))
+    }
+
+    fn files(&self) -> Result<Vec<PartitionedFile>> {
+        let partition_schema = self.0.partition_schema();
+        self.0
+            .files()
+            .iter()
+            .map(|file| {
+                let mut partitioned = PartitionedFile::new(file.path.clone(), file.size);
+                partitioned.partition_values = partition_schema
+                    .fields()
+                    .iter()
+                    .map(|field| match file.partition_value(field.name()) {
+                        Some(value) => {
+                            ScalarValue::try_from_string(value.to_string(), field.data_type())
+                        }
+                        None => ScalarValue::try_from(field.data_type()),
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(partitioned)
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TableProvider for DeltaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.0.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let partition_cols = self
+            .0
+            .partition_schema()
+            .fields()
+            .iter()
+            .map(|f| f.as_ref().clone())
+            .collect();
+        let config = FileScanConfig::new(ObjectStoreUrl::local_filesystem(), self.0.file_schema())
+            .with_file_group(self.files()?)
+            .with_table_partition_cols(partition_cols)
+            .with_projection(projection.cloned())
+            .with_limit(limit);
+
+        let mut builder = ParquetExec::builder(config);
+        if let Some(filter) = conjunction(filters.to_vec()) {
+            let df_schema = self.schema().to_dfschema()?;
+            builder = builder.with_predicate(state.create_physical_expr(filter, &df_schema)?);
+        }
+        Ok(builder.build_arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::delta::tests::{events, relative};
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::prelude::SessionContext;
+
+    #[tokio::test]
+    async fn delta_provider_should_scan_relative_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        events(dir.path());
+        let provider = DeltaProvider::try_new(&relative(dir.path()), &SnapshotOpts::default());
+        let ctx = SessionContext::new();
+        ctx.register_table("events", Arc::new(provider.unwrap()))
+            .unwrap();
+        let sql = "select id, day from events where day = 'a' or id = 3 order by id";
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        let expected = [
+            "+----+-----+",
+            "| id | day |",
+            "+----+-----+",
+            "| 3  | b   |",
+            "| 10 | a   |",
+            "+----+-----+",
+        ];
+        let table = pretty_format_batches(&batches).unwrap().to_string();
+        assert_eq!(table, expected.join("\n"));
+    }
+}
@@ -75,7 +75,7 @@ impl Describer {
             Some(a) => {
                 let _ = a.iter().map(|m| match m {
                     Aggregator::Percentile(p) if p > &100 => {
-                        return Err(anyhow!("percentile should be [0, 100], but got {}", p))
+                        Err(anyhow!("percentile should be [0, 100], but got {}", p))
                     }
                     _ => Ok::<_, anyhow::Error>(()),
                 });
@@ -103,15 +103,14 @@ impl Describer {
                     Aggregator::Min => self.min().unwrap(),
                     Aggregator::Max => self.max().unwrap(),
                     Aggregator::Median => self.median().unwrap(),
-                    Aggregator::Percentile(p) => self.percentile(p.clone()).unwrap(),
+                    Aggregator::Percentile(p) => self.percentile(*p).unwrap(),
                 };
                 let mut select_expr = vec![lit(m.to_string()).alias("describe")];
                 select_expr.extend(d.schema().fields().iter().map(|f| col(f.name())));
                 let d = d.select(select_expr).unwrap();
-                if acc.is_none() {
-                    Some(d)
-                } else {
-                    Some(acc.unwrap().union(d).unwrap())
+                match acc {
+                    None => Some(d),
+                    Some(acc) => Some(acc.union(d).unwrap()),
                 }
             })
             .unwrap();
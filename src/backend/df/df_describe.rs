@@ -44,7 +44,7 @@ impl DescribeDataFrame {
     pub(crate) async fn to_record_batch(&self) -> anyhow::Result<RecordBatch> {
         let supported_describe_functions = self.functions.clone();
         let original_schema_fields = self.df.schema().fields().iter();
-        let dfs = vec![
+        let dfs = [
             self.count(),
             self.null_count(),
             self.mean(),
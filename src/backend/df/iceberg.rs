@@ -0,0 +1,266 @@
+use crate::backend::iceberg::{IcebergFile, IcebergTable};
+use crate::cli::connect::{IcebergLocation, SnapshotOpts};
+use async_trait::async_trait;
+use datafusion::arrow::array::{ArrayRef, BooleanArray, UInt64Array};
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::{Column, ScalarValue, ToDFSchema};
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::datasource::schema_adapter::{
+    DefaultSchemaAdapterFactory, SchemaAdapter, SchemaAdapterFactory, SchemaMapper,
+};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+use datafusion::physical_plan::ExecutionPlan;
+use std::any::Any;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A snapshot of an Iceberg table, files whose identity partitions can't match the filters
+/// are skipped and row groups of the others are pruned by the parquet reader. Filters are
+/// applied again on the rows read.
+#[derive(Debug)]
+pub struct IcebergProvider(Arc<IcebergTable>);
+
+impl IcebergProvider {
+    pub fn try_new(
+        location: &IcebergLocation,
+        table: Option<&str>,
+        opts: &SnapshotOpts,
+    ) -> anyhow::Result<Self> {
+        Ok(Self(Arc::new(IcebergTable::try_new(
+            location, table, opts,
+        )?)))
+    }
+}
+
+#[async_trait]
+impl TableProvider for IcebergProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.0.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let df_schema = self.schema().to_dfschema()?;
+        let predicate = conjunction(filters.to_vec())
+            .map(|filter| state.create_physical_expr(filter, &df_schema))
+            .transpose()?;
+
+        let mut keep = vec![true; self.0.files().len()];
+        if let Some(predicate) = &predicate {
+            let pruning = PruningPredicate::try_new(predicate.clone(), self.schema())?;
+            keep = pruning.prune(&PartitionStatistics(self.0.files()))?;
+        }
+        let files = self
+            .0
+            .files()
+            .iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(file, _)| file)
+            .collect::<Vec<_>>();
+        // row groups are pruned by the statistics of the file columns named like the filtered
+        // ones, which are other columns in files written before a column was renamed
+        let mut renamed = false;
+        for file in &files {
+            let schema = file
+                .schema()
+                .map_err(|e| DataFusionError::External(e.into()))?;
+            renamed |= self.0.is_renamed(&schema);
+        }
+        let files = files
+            .into_iter()
+            .map(|file| PartitionedFile::new(file.path.clone(), file.size))
+            .collect();
+
+        let config = FileScanConfig::new(ObjectStoreUrl::local_filesystem(), self.schema())
+            .with_file_group(files)
+            .with_projection(projection.cloned())
+            .with_limit(limit);
+        let mut builder = ParquetExec::builder(config)
+            .with_schema_adapter_factory(Arc::new(FieldIdAdapterFactory(self.0.clone())));
+        if let Some(predicate) = predicate.filter(|_| !renamed) {
+            builder = builder.with_predicate(predicate);
+        }
+        Ok(builder.build_arc())
+    }
+}
+
+/// Columns of the data files are given the name of the table column of their field id,
+/// then matched to the table by name like any parquet file
+#[derive(Debug)]
+struct FieldIdAdapterFactory(Arc<IcebergTable>);
+
+impl SchemaAdapterFactory for FieldIdAdapterFactory {
+    fn create(
+        &self,
+        projected_table_schema: SchemaRef,
+        table_schema: SchemaRef,
+    ) -> Box<dyn SchemaAdapter> {
+        Box::new(FieldIdAdapter {
+            table: self.0.clone(),
+            adapter: DefaultSchemaAdapterFactory.create(projected_table_schema, table_schema),
+        })
+    }
+}
+
+struct FieldIdAdapter {
+    table: Arc<IcebergTable>,
+    adapter: Box<dyn SchemaAdapter>,
+}
+
+impl FieldIdAdapter {
+    /// Columns dropped from the table get a name no table column has
+    fn rename(&self, file_schema: &Schema) -> Schema {
+        let fields = file_schema.fields().iter().map(|f| {
+            let name = match self.table.table_column(f) {
+                Some(column) => column.to_string(),
+                None => format!("{} (dropped)", f.name()),
+            };
+            f.as_ref().clone().with_name(name)
+        });
+        Schema::new(fields.collect::<Vec<_>>())
+    }
+}
+
+impl SchemaAdapter for FieldIdAdapter {
+    fn map_column_index(&self, index: usize, file_schema: &Schema) -> Option<usize> {
+        self.adapter
+            .map_column_index(index, &self.rename(file_schema))
+    }
+
+    fn map_schema(&self, file_schema: &Schema) -> Result<(Arc<dyn SchemaMapper>, Vec<usize>)> {
+        self.adapter.map_schema(&self.rename(file_schema))
+    }
+}
+
+/// The value of an identity partition is both the min and the max of its column in a file,
+/// unknown for files written with a spec that doesn't partition by the column
+struct PartitionStatistics<'a>(&'a [IcebergFile]);
+
+impl PartitionStatistics<'_> {
+    fn values(&self, column: &Column) -> Option<ArrayRef> {
+        let data_type = self
+            .0
+            .iter()
+            .find_map(|f| f.partition.get(&column.name))?
+            .data_type();
+        let values = self.0.iter().map(|f| match f.partition.get(&column.name) {
+            Some(value) => Ok(value.clone()),
+            None => ScalarValue::try_from(&data_type),
+        });
+        let values = values.collect::<Result<Vec<_>>>().ok()?;
+        ScalarValue::iter_to_array(values).ok()
+    }
+}
+
+impl PruningStatistics for PartitionStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.values(column)
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.values(column)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.0.len()
+    }
+
+    fn null_counts(&self, _column: &Column) -> Option<ArrayRef> {
+        None
+    }
+
+    fn row_counts(&self, _column: &Column) -> Option<ArrayRef> {
+        let counts = UInt64Array::from_iter_values(self.0.iter().map(|f| f.record_count));
+        Some(Arc::new(counts))
+    }
+
+    fn contained(&self, _column: &Column, _values: &HashSet<ScalarValue>) -> Option<BooleanArray> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::prelude::{col, lit, SessionContext};
+    use std::collections::HashMap;
+
+    fn file(path: &str, day: Option<&str>) -> IcebergFile {
+        let partition = day.map(|d| ("day".to_string(), ScalarValue::from(d)));
+        IcebergFile {
+            path: path.to_string(),
+            size: 100,
+            record_count: 10,
+            partition: partition.into_iter().collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn prune(files: &[IcebergFile], filter: Expr) -> Vec<bool> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("day", DataType::Utf8, true),
+        ]));
+        let state = SessionContext::new().state();
+        let df_schema = schema.clone().to_dfschema().unwrap();
+        let predicate = state.create_physical_expr(filter, &df_schema).unwrap();
+        let pruning = PruningPredicate::try_new(predicate, schema).unwrap();
+        pruning.prune(&PartitionStatistics(files)).unwrap()
+    }
+
+    #[test]
+    fn partition_statistics_should_prune_files_of_other_partitions() {
+        // the last file was written with a spec that doesn't partition by day
+        let files = [
+            file("a.parquet", Some("a")),
+            file("b.parquet", Some("b")),
+            file("c.parquet", Some("c")),
+            file("all.parquet", None),
+        ];
+        assert_eq!(
+            prune(&files, col("day").eq(lit("b"))),
+            [false, true, false, true]
+        );
+        assert_eq!(
+            prune(&files, col("day").gt_eq(lit("b"))),
+            [false, true, true, true]
+        );
+        assert_eq!(
+            prune(&files, col("day").eq(lit("a")).or(col("day").eq(lit("c")))),
+            [true, false, true, true]
+        );
+        // columns that aren't partitions keep every file
+        assert_eq!(prune(&files, col("id").eq(lit(1i64))), [true; 4]);
+        // no file has a partition value, nothing is known
+        let files = [file("x.parquet", None), file("y.parquet", None)];
+        assert_eq!(prune(&files, col("day").eq(lit("b"))), [true, true]);
+    }
+}
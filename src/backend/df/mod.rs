@@ -1,17 +1,55 @@
+// DataFusion's errors grow past the size clippy allows for an `Err` with the avro feature, and
+// they are what the providers have to return
+#![allow(clippy::result_large_err)]
+
+mod delta;
 mod describe;
 pub mod df_describe;
+mod iceberg;
+mod orc;
+mod postgres;
+mod sqlite;
 
+use crate::backend::archive::archive_members;
+use crate::backend::df::delta::DeltaProvider;
 use crate::backend::df::describe::Describer;
-use crate::cli::connect::DataSetConn;
-use crate::cli::{ConnectOpts, DescribeOpts, HeadOpts, SchemaOpts, SqlOpts};
+use crate::backend::df::iceberg::IcebergProvider;
+use crate::backend::df::orc::OrcProvider;
+use crate::backend::df::postgres::PostgresTable;
+use crate::backend::df::sqlite::SqliteProvider;
+use crate::backend::excel::read_excel;
+use crate::backend::fixed_width::read_fixed_width;
+use crate::backend::json::{is_json_document, read_json_document};
+use crate::backend::logs::read_log;
+use crate::backend::sqlite::SqliteTable;
+use crate::backend::store::remote_store;
+use crate::backend::xml::read_xml;
+use crate::cli::connect::{is_remote, CsvOpts, DataSetConn, FileOpts};
+use crate::cli::{ConnectOpts, DescribeOpts, ExportOpts, HeadOpts, SchemaOpts, SqlOpts};
 use crate::{Backend, ReplDisplay};
-use datafusion::arrow::array::RecordBatch;
+use anyhow::anyhow;
+use datafusion::arrow::array::{AsArray, RecordBatch};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, UInt64Type};
+use datafusion::arrow::ipc::writer::FileWriter;
 use datafusion::arrow::util::pretty::pretty_format_batches;
+use datafusion::catalog::SchemaProvider;
+use datafusion::catalog_common::MemorySchemaProvider;
+use datafusion::config::{CsvOptions, JsonOptions};
 use datafusion::dataframe::DataFrame;
+use datafusion::dataframe::DataFrameWriteOptions;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::datasource::MemTable;
+use datafusion::execution::options::ArrowReadOptions;
+use datafusion::execution::options::AvroReadOptions;
 use datafusion::prelude::{
-    CsvReadOptions, NdJsonReadOptions, ParquetReadOptions, SessionConfig, SessionContext,
+    cast, ident, lit, when, CsvReadOptions, NdJsonReadOptions, ParquetReadOptions, SessionConfig,
+    SessionContext,
 };
+use datafusion::scalar::ScalarValue;
+use futures::StreamExt;
+use std::fs::File;
 use std::ops::Deref;
+use std::sync::Arc;
 
 pub struct DataFusionBackend(SessionContext);
 
@@ -24,6 +62,151 @@ impl DataFusionBackend {
     }
 }
 
+impl DataFusionBackend {
+    async fn register_postgres(&self, name: &str, conn: &str, table: &str) -> anyhow::Result<()> {
+        let provider = PostgresTable::try_new(conn, table).await?;
+        self.register_table(name, Arc::new(provider))?;
+        Ok(())
+    }
+
+    /// Hive style `key=value` directories of a directory or glob become partition columns,
+    /// typed as strings since they are only known from the paths
+    async fn partition_cols(
+        &self,
+        file_opts: &FileOpts,
+    ) -> anyhow::Result<Vec<(String, DataType)>> {
+        let cols = partition_names(&self.0, file_opts).await?;
+        Ok(cols.into_iter().map(|col| (col, DataType::Utf8)).collect())
+    }
+
+    /// Without a table every table of the file is registered in a schema named after the dataset
+    fn register_sqlite(&self, name: &str, path: &str, table: Option<&str>) -> anyhow::Result<()> {
+        if let Some(table) = table {
+            self.register_table(name, Arc::new(SqliteProvider::try_new(path, table)?))?;
+            return Ok(());
+        }
+        let schema = self.register_schema(name)?;
+        for table in SqliteTable::tables(path)? {
+            let provider = SqliteProvider::try_new(path, &table)?;
+            schema.register_table(table, Arc::new(provider))?;
+        }
+        Ok(())
+    }
+
+    /// An empty schema named after the dataset in the default catalog
+    fn register_schema(&self, name: &str) -> anyhow::Result<Arc<dyn SchemaProvider>> {
+        let schema = Arc::new(MemorySchemaProvider::new());
+        let catalog_name = self
+            .state()
+            .config_options()
+            .catalog
+            .default_catalog
+            .clone();
+        let catalog = self
+            .catalog(&catalog_name)
+            .ok_or_else(|| anyhow!("catalog {} not found", catalog_name))?;
+        catalog.register_schema(name, schema.clone())?;
+        Ok(schema)
+    }
+
+    /// DataFusion only skips the null value when inferring the schema, so with one the fields
+    /// are read as strings, those matching it made null and the others cast to the schema
+    async fn register_csv_file(
+        &self,
+        opts: &ConnectOpts,
+        file_opts: &FileOpts,
+    ) -> anyhow::Result<()> {
+        let csv_opts = CsvReadOptions {
+            table_partition_cols: self.partition_cols(file_opts).await?,
+            schema: opts.schema.as_deref(),
+            ..csv_read_options(file_opts, &opts.csv)
+        };
+        let Some(null_value) = &opts.csv.null_value else {
+            self.register_csv(&opts.name, &file_opts.filename, csv_opts)
+                .await?;
+            return Ok(());
+        };
+        let schema = self
+            .read_csv(&file_opts.filename, csv_opts.clone())
+            .await?
+            .schema()
+            .as_arrow()
+            .clone();
+        let is_partition =
+            |name: &str| csv_opts.table_partition_cols.iter().any(|(p, _)| p == name);
+        let strings = schema
+            .fields()
+            .iter()
+            .filter(|f| !is_partition(f.name()))
+            .map(|f| Field::new(f.name(), DataType::Utf8, true))
+            .collect::<Vec<_>>();
+        let strings = Schema::new(strings);
+        let df = self
+            .read_csv(
+                &file_opts.filename,
+                CsvReadOptions {
+                    schema: Some(&strings),
+                    ..csv_opts.clone()
+                },
+            )
+            .await?;
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| {
+                let column = ident(f.name());
+                if is_partition(f.name()) {
+                    return Ok(column);
+                }
+                let value = when(
+                    column.clone().eq(lit(null_value.as_str())),
+                    lit(ScalarValue::Utf8(None)),
+                )
+                .otherwise(column)?;
+                Ok(cast(value, f.data_type().clone()).alias(f.name()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.register_table(&opts.name, df.select(columns)?.into_view())?;
+        Ok(())
+    }
+
+    /// A single member is registered as the dataset, several as tables of a schema named
+    /// after the dataset
+    async fn register_archive(
+        &mut self,
+        opts: &ConnectOpts,
+        archive: &FileOpts,
+    ) -> anyhow::Result<()> {
+        let members = archive_members(opts, archive)?;
+        if let [member] = members.as_slice() {
+            let member = ConnectOpts {
+                name: opts.name.clone(),
+                ..member.clone()
+            };
+            return Box::pin(self.connect(&member)).await;
+        }
+        self.register_schema(&opts.name)?;
+        for member in members {
+            let member = ConnectOpts {
+                name: format!("{}.{}", opts.name, member.name),
+                ..member
+            };
+            Box::pin(self.connect(&member)).await?;
+        }
+        Ok(())
+    }
+
+    /// Objects are read through the store of their bucket or server, built again on every connect so
+    /// changed credentials or endpoints are picked up
+    fn register_store(&self, conn: &DataSetConn) -> anyhow::Result<()> {
+        if let Some(url) = remote_url(conn) {
+            let (store_url, store) = remote_store(url)?;
+            self.register_object_store(store_url.as_ref(), store);
+        }
+        Ok(())
+    }
+}
+
 impl Default for DataFusionBackend {
     fn default() -> Self {
         Self::new()
@@ -40,31 +223,100 @@ impl Deref for DataFusionBackend {
 
 impl Backend for DataFusionBackend {
     async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
-        match &opts.conn {
-            DataSetConn::Postgres(_s) => {
-                // self.register_postgres(opts.table, opts.name).await
-                println!(
-                    "postgres not supported for now: {:?} {}",
-                    opts.table, opts.name
-                );
+        self.register_store(opts.conn())?;
+        match opts.conn() {
+            DataSetConn::Postgres(conn) => {
+                let table = opts
+                    .table
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("table name is required for postgres, use -t"))?;
+                self.register_postgres(&opts.name, conn, table).await?;
+            }
+            DataSetConn::Sqlite(path) => {
+                self.register_sqlite(&opts.name, path, opts.table.as_deref())?
             }
-            DataSetConn::Parquet(filename) => {
-                self.register_parquet(&opts.name, &filename, ParquetReadOptions::new())
+            DataSetConn::Parquet(file_opts) => {
+                let parquet_opts = ParquetReadOptions {
+                    file_extension: &file_opts.ext,
+                    table_partition_cols: self.partition_cols(file_opts).await?,
+                    ..Default::default()
+                };
+                self.register_parquet(&opts.name, &file_opts.filename, parquet_opts)
                     .await?;
             }
-            DataSetConn::Csv(file_opts) => {
-                let csv_opts = CsvReadOptions {
+            DataSetConn::Arrow(file_opts) => {
+                let arrow_opts = ArrowReadOptions {
                     file_extension: &file_opts.ext,
-                    file_compression_type: file_opts.compression,
+                    table_partition_cols: self.partition_cols(file_opts).await?,
                     ..Default::default()
                 };
-                self.register_csv(&opts.name, &file_opts.filename, csv_opts)
+                self.register_arrow(&opts.name, &file_opts.filename, arrow_opts)
                     .await?;
             }
+            DataSetConn::Avro(filename) => {
+                // a single file is registered whatever the case of its extension
+                let avro_opts = AvroReadOptions {
+                    file_extension: "",
+                    ..Default::default()
+                };
+                self.register_avro(&opts.name, filename, avro_opts).await?;
+            }
+            DataSetConn::Orc(filename) => {
+                let provider = OrcProvider::try_new(filename)?;
+                self.register_table(&opts.name, Arc::new(provider))?;
+            }
+            DataSetConn::Archive(archive) => self.register_archive(opts, archive).await?,
+            DataSetConn::Stdin => return Err(anyhow!("standard input is read before connecting")),
+            DataSetConn::Delta(location) => {
+                let provider = DeltaProvider::try_new(location, &opts.snapshot)?;
+                self.register_table(&opts.name, Arc::new(provider))?;
+            }
+            DataSetConn::Iceberg(location) => {
+                let table = opts.table.as_deref();
+                let provider = IcebergProvider::try_new(location, table, &opts.snapshot)?;
+                self.register_table(&opts.name, Arc::new(provider))?;
+            }
+            DataSetConn::Excel(filename) => {
+                let batch = read_excel(filename, &opts.excel)?;
+                let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+                self.register_table(&opts.name, Arc::new(table))?;
+            }
+            DataSetConn::Csv(file_opts) => self.register_csv_file(opts, file_opts).await?,
+            DataSetConn::FixedWidth(file_opts) => {
+                let layout = opts
+                    .layout
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("--layout is required for fixed-width files"))?;
+                let (schema, batches) = read_fixed_width(file_opts, layout)?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(&opts.name, Arc::new(table))?;
+            }
+            DataSetConn::Log(file_opts) => {
+                let regex = opts
+                    .regex
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("--regex is required for log files"))?;
+                let (schema, batches) = read_log(file_opts, regex, opts.schema.as_ref())?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(&opts.name, Arc::new(table))?;
+            }
+            DataSetConn::Xml(file_opts) => {
+                let (schema, batches) = read_xml(file_opts, &opts.xml, opts.schema.as_ref())?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(&opts.name, Arc::new(table))?;
+            }
+            DataSetConn::Json(file_opts) if is_json_document(file_opts, &opts.json)? => {
+                let (schema, batches) =
+                    read_json_document(file_opts, &opts.json, opts.schema.as_ref())?;
+                let table = MemTable::try_new(schema, vec![batches])?;
+                self.register_table(&opts.name, Arc::new(table))?;
+            }
             DataSetConn::Json(file_opts) => {
                 let json_opt = NdJsonReadOptions {
                     file_extension: &file_opts.ext,
                     file_compression_type: file_opts.compression,
+                    table_partition_cols: self.partition_cols(file_opts).await?,
+                    schema: opts.schema.as_deref(),
                     ..Default::default()
                 };
                 self.register_json(&opts.name, &file_opts.filename, json_opt)
@@ -77,7 +329,8 @@ impl Backend for DataFusionBackend {
     async fn list(&self) -> anyhow::Result<impl ReplDisplay> {
         let df = self
             .0
-            .sql("select table_name, table_type from information_schema.tables where table_schema='public'")
+            .sql("select case when table_schema = 'public' then table_name else table_schema || '.' || table_name end as table_name, table_type \
+                  from information_schema.tables where table_schema <> 'information_schema' order by table_schema <> 'public', table_schema, table_name")
             .await?;
         Ok(df)
     }
@@ -113,6 +366,153 @@ impl Backend for DataFusionBackend {
         let df = self.0.sql(&opts.sql).await?;
         Ok(df)
     }
+
+    async fn export(&self, opts: ExportOpts) -> anyhow::Result<usize> {
+        let df = self.0.sql(&opts.sql()).await?;
+        write_dataframe(df, &opts.target).await
+    }
+}
+
+/// The keys of the `key=value` directories of the first file of a listing, the partition
+/// columns of both backends
+pub(crate) async fn partition_names(
+    ctx: &SessionContext,
+    file_opts: &FileOpts,
+) -> anyhow::Result<Vec<String>> {
+    let url = ListingTableUrl::parse(&file_opts.filename)?;
+    let state = ctx.state();
+    let store = state.runtime_env().object_store(&url)?;
+    let first = url
+        .list_all_files(&state, store.as_ref(), &file_opts.ext)
+        .await?
+        .next()
+        .await
+        .transpose()?;
+    let Some(file) = first else {
+        return Ok(vec![]);
+    };
+
+    let prefix = url.prefix().as_ref();
+    let relative = file.location.as_ref()[prefix.len()..].trim_start_matches('/');
+    let cols = relative
+        .split('/')
+        .rev()
+        .skip(1)
+        .filter_map(|dir| dir.split_once('=').map(|(key, _)| key.to_string()))
+        .collect::<Vec<_>>();
+    Ok(cols.into_iter().rev().collect())
+}
+
+fn csv_read_options<'a>(file_opts: &'a FileOpts, csv: &CsvOpts) -> CsvReadOptions<'a> {
+    let default = CsvReadOptions::default();
+    CsvReadOptions {
+        has_header: !csv.no_header,
+        delimiter: csv.delimiter.unwrap_or(default.delimiter),
+        quote: csv.quote.unwrap_or(default.quote),
+        escape: csv.escape,
+        comment: csv.comment,
+        terminator: csv.terminator,
+        // the regex isn't anchored by the csv reader, so `NA` would match `NAME` as well. Fields
+        // aren't trimmed either, ` NA` stays a string like ` 1` isn't read as a number. Empty
+        // fields are only null without a regex, so it matches them too
+        null_regex: csv
+            .null_value
+            .as_ref()
+            .map(|v| format!("^({})?$", regex::escape(v))),
+        schema_infer_max_records: csv
+            .schema_infer_rows
+            .unwrap_or(default.schema_infer_max_records),
+        file_extension: &file_opts.ext,
+        file_compression_type: file_opts.compression,
+        ..default
+    }
+}
+
+/// The url of a dataset read from or written to an object store
+fn remote_url(conn: &DataSetConn) -> Option<&str> {
+    match conn {
+        DataSetConn::Parquet(file_opts)
+        | DataSetConn::Arrow(file_opts)
+        | DataSetConn::Csv(file_opts)
+        | DataSetConn::Json(file_opts) => {
+            Some(file_opts.filename.as_str()).filter(|f| is_remote(f))
+        }
+        _ => None,
+    }
+}
+
+/// Write the dataframe to a single file, returns the number of rows written
+pub(crate) async fn write_dataframe(df: DataFrame, target: &DataSetConn) -> anyhow::Result<usize> {
+    if let Some(url) = remote_url(target) {
+        if matches!(target, DataSetConn::Arrow(_)) {
+            return Err(anyhow!(
+                "export of arrow files to object stores is not supported"
+            ));
+        }
+        let (store_url, store) = remote_store(url)?;
+        df.task_ctx()
+            .runtime_env()
+            .register_object_store(store_url.as_ref(), store);
+    }
+    let options = DataFrameWriteOptions::new().with_single_file_output(true);
+    let ret = match target {
+        DataSetConn::Postgres(_) => return Err(anyhow!("export to postgres is not supported")),
+        DataSetConn::Sqlite(_) => return Err(anyhow!("export to sqlite is not supported")),
+        DataSetConn::Parquet(file_opts) => {
+            df.write_parquet(&file_opts.filename, options, None).await?
+        }
+        DataSetConn::Avro(_) => return Err(anyhow!("export to avro is not supported")),
+        DataSetConn::Orc(_) => return Err(anyhow!("export to orc is not supported")),
+        DataSetConn::Excel(_) => return Err(anyhow!("export to excel is not supported")),
+        DataSetConn::Archive(_) => return Err(anyhow!("export to archives is not supported")),
+        DataSetConn::FixedWidth(_) => {
+            return Err(anyhow!("export to fixed-width files is not supported"))
+        }
+        DataSetConn::Log(_) => return Err(anyhow!("export to log files is not supported")),
+        DataSetConn::Xml(_) => return Err(anyhow!("export to xml is not supported")),
+        DataSetConn::Stdin => return Err(anyhow!("export to standard input is not supported")),
+        DataSetConn::Delta(_) => return Err(anyhow!("export to delta tables is not supported")),
+        DataSetConn::Iceberg(_) => {
+            return Err(anyhow!("export to iceberg tables is not supported"))
+        }
+        DataSetConn::Arrow(file_opts) => return write_arrow(df, &file_opts.filename).await,
+        DataSetConn::Csv(file_opts) => {
+            let csv_opts = CsvOptions::default()
+                .with_has_header(true)
+                .with_file_compression_type(*file_opts.compression.get_variant());
+            df.write_csv(&file_opts.filename, options, Some(csv_opts))
+                .await?
+        }
+        DataSetConn::Json(file_opts) => {
+            let json_opts = JsonOptions {
+                compression: *file_opts.compression.get_variant(),
+                ..Default::default()
+            };
+            df.write_json(&file_opts.filename, options, Some(json_opts))
+                .await?
+        }
+    };
+    let rows = ret
+        .first()
+        .and_then(|b| b.column(0).as_primitive_opt::<UInt64Type>())
+        .map(|c| c.value(0) as usize)
+        .unwrap_or_default();
+    Ok(rows)
+}
+
+/// DataFusion has no arrow file sink, batches are written one by one with the IPC file writer
+async fn write_arrow(df: DataFrame, filename: &str) -> anyhow::Result<usize> {
+    let schema = Arc::new(df.schema().as_arrow().clone());
+    let mut stream = df.execute_stream().await?;
+    let mut writer = FileWriter::try_new(File::create(filename)?, &schema)?;
+    let mut rows = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        rows += batch.num_rows();
+        writer.write(&batch)?;
+    }
+    writer.finish()?;
+    Ok(rows)
 }
 
 impl ReplDisplay for DataFrame {
@@ -129,3 +529,177 @@ impl ReplDisplay for RecordBatch {
         Ok(ret.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::connect::{conn_parser, target_parser};
+    use crate::CmdExecutor;
+    use clap::Parser;
+
+    async fn backend_with_sales(dir: &std::path::Path) -> anyhow::Result<DataFusionBackend> {
+        let path = dir.join("sales.csv");
+        std::fs::write(
+            &path,
+            "id,region,total\n1,north,10.5\n2,south,\n3,north,7\n",
+        )?;
+        let mut backend = DataFusionBackend::new();
+        let conn = conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        backend
+            .connect(&ConnectOpts::new(conn, None, "sales".to_string()))
+            .await?;
+        Ok(backend)
+    }
+
+    #[tokio::test]
+    async fn connect_should_read_csv_files_with_the_given_dialect() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sales.csv");
+        let csv = "# exported on 2024-05-01\n1;north;10.5\n2;NA;NA\n# south\n3; NA;7\n4;NAME;\n";
+        std::fs::write(&path, csv)?;
+        let args = [
+            "connect",
+            path.to_str().unwrap(),
+            "-n",
+            "sales",
+            "--delimiter",
+            ";",
+            "--no-header",
+            "--null-value",
+            "NA",
+            "--comment",
+            "#",
+        ];
+        let opts = ConnectOpts::try_parse_from(args)?;
+        let mut backend = DataFusionBackend::new();
+        Box::new(opts).execute(&mut backend).await?;
+
+        let sql = "SELECT *, arrow_typeof(column_3) AS type FROM sales ORDER BY column_1";
+        let out = backend.sql(SqlOpts::new(sql.to_string())).await?;
+        // null values are whole fields, the padded one and NAME are strings
+        let expected = [
+            "+----------+----------+----------+---------+",
+            "| column_1 | column_2 | column_3 | type    |",
+            "+----------+----------+----------+---------+",
+            "| 1        | north    | 10.5     | Float64 |",
+            "| 2        |          |          | Float64 |",
+            "| 3        |  NA      | 7.0      | Float64 |",
+            "| 4        | NAME     |          | Float64 |",
+            "+----------+----------+----------+---------+",
+        ];
+        assert_eq!(out.display().await?, expected.join("\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_should_read_avro_files_with_nested_records_and_unions() -> anyhow::Result<()> {
+        use apache_avro::types::{Record, Value};
+
+        let schema = apache_avro::Schema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "sale",
+                "fields": [
+                    {"name": "id", "type": "long"},
+                    {"name": "region", "type": ["null", "string"]},
+                    {"name": "shop", "type": {
+                        "type": "record",
+                        "name": "shop",
+                        "fields": [{"name": "city", "type": "string"}]
+                    }}
+                ]
+            }"#,
+        )?;
+        let mut writer =
+            apache_avro::Writer::with_codec(&schema, vec![], apache_avro::Codec::Snappy);
+        for (id, region) in [(1, Some("north")), (2, None)] {
+            let region = match region {
+                Some(r) => Value::Union(1, Box::new(Value::String(r.to_string()))),
+                None => Value::Union(0, Box::new(Value::Null)),
+            };
+            let shop = Value::Record(vec![("city".into(), Value::String("Oslo".into()))]);
+            let mut record = Record::new(&schema).unwrap();
+            record.put("id", Value::Long(id));
+            record.put("region", region);
+            record.put("shop", shop);
+            writer.append(record)?;
+        }
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sales.AVRO");
+        std::fs::write(&path, writer.into_inner()?)?;
+
+        let mut backend = DataFusionBackend::new();
+        let conn = conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        backend
+            .connect(&ConnectOpts::new(conn, None, "sales".to_string()))
+            .await?;
+        let sql = "SELECT id, region, shop['city'] AS city FROM sales ORDER BY id";
+        let out = backend.sql(SqlOpts::new(sql.to_string())).await?;
+        let expected = [
+            "+----+--------+------+",
+            "| id | region | city |",
+            "+----+--------+------+",
+            "| 1  | north  | Oslo |",
+            "| 2  |        | Oslo |",
+            "+----+--------+------+",
+        ];
+        assert_eq!(out.display().await?, expected.join("\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_should_write_files_that_read_back_the_same() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut backend = backend_with_sales(dir.path()).await?;
+        let sql = SqlOpts::new("SELECT * FROM sales ORDER BY id".to_string());
+        let expected = backend.sql(sql).await?.display().await?;
+
+        for name in ["out.parquet", "out.csv.gz", "out.json", "out.arrow"] {
+            let path = dir.path().join(name).to_string_lossy().to_string();
+            let target = target_parser(&path).map_err(|e| anyhow!(e))?;
+            let opts = ExportOpts::new("sales".to_string(), target, false);
+            assert_eq!(backend.export(opts).await?, 3, "{}", name);
+
+            let conn = conn_parser(&path).map_err(|e| anyhow!(e))?;
+            let table = name.replace('.', "_");
+            backend
+                .connect(&ConnectOpts::new(conn, None, table.clone()))
+                .await?;
+            let sql = SqlOpts::new(format!("SELECT * FROM {} ORDER BY id", table));
+            let out = backend.sql(sql).await?.display().await?;
+            assert_eq!(out, expected, "{}", name);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_should_run_the_source_as_a_query_with_sql() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backend = backend_with_sales(dir.path()).await?;
+        let path = dir.path().join("north.csv");
+        let target = target_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let sql = "SELECT id FROM sales WHERE region = 'north'".to_string();
+        let opts = ExportOpts::new(sql, target, true);
+        assert_eq!(backend.export(opts).await?, 2);
+        assert_eq!(std::fs::read_to_string(&path)?, "id\n1\n3\n");
+
+        // without --sql the source is a dataset name, even a single word query
+        let target = target_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let opts = ExportOpts::new("VALUES(1)".to_string(), target, false);
+        assert_eq!(opts.sql(), "SELECT * FROM VALUES(1)");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_should_reject_unsupported_targets() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let backend = backend_with_sales(dir.path()).await?;
+        let path = dir.path().join("out.xml");
+        let target = target_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let opts = ExportOpts::new("sales".to_string(), target, false);
+        let err = backend.export(opts).await.unwrap_err();
+        assert_eq!(err.to_string(), "export to xml is not supported");
+        assert!(!path.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::backend::orc::OrcFile;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::datasource::streaming::StreamingTable;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
+use datafusion::physical_plan::ExecutionPlan;
+use futures::{stream, TryStreamExt};
+use std::any::Any;
+use std::sync::Arc;
+
+/// An ORC file scanned one stripe per partition, only the projected columns are read from
+/// each stripe.
+#[derive(Debug)]
+pub struct OrcProvider(Arc<OrcFile>);
+
+impl OrcProvider {
+    pub fn try_new(filename: &str) -> anyhow::Result<Self> {
+        Ok(Self(Arc::new(OrcFile::try_new(filename)?)))
+    }
+}
+
+#[async_trait]
+impl TableProvider for OrcProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.0.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = match projection {
+            Some(projection) => Arc::new(self.0.schema().project(projection)?),
+            None => self.0.schema(),
+        };
+        if self.0.num_stripes() == 0 {
+            return Ok(Arc::new(EmptyExec::new(schema)));
+        }
+        let stripes = (0..self.0.num_stripes())
+            .map(|stripe| {
+                Arc::new(StripeStream {
+                    file: self.0.clone(),
+                    stripe,
+                    projection: projection.cloned(),
+                    schema: schema.clone(),
+                }) as Arc<dyn PartitionStream>
+            })
+            .collect();
+        StreamingTable::try_new(schema, stripes)?
+            .scan(state, None, filters, limit)
+            .await
+    }
+}
+
+#[derive(Debug)]
+struct StripeStream {
+    file: Arc<OrcFile>,
+    stripe: usize,
+    projection: Option<Vec<usize>>,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for StripeStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let file = self.file.clone();
+        let stripe = self.stripe;
+        let projection = self.projection.clone();
+        let batches = stream::once(async move {
+            let batches = file
+                .read_stripe(stripe, projection.as_deref())
+                .map_err(|e| DataFusionError::External(e.into()))?
+                .map(|batch| batch.map_err(|e| DataFusionError::External(e.into())));
+            Ok::<_, DataFusionError>(stream::iter(batches))
+        })
+        .try_flatten();
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), batches))
+    }
+}
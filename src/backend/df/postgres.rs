@@ -0,0 +1,780 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use datafusion::arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, ListArray, ListBuilder, RecordBatch, RecordBatchOptions, StringArray,
+    StringBuilder, Time64MicrosecondArray, TimestampMicrosecondArray,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{
+    DataType, Decimal128Type, DecimalType, Field, Float32Type, Float64Type, Int16Type, Int32Type,
+    Int64Type, Schema, SchemaRef, TimeUnit, DECIMAL128_MAX_PRECISION,
+};
+use datafusion::catalog::Session;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Expr, Like, Operator, TableProviderFilterPushDown,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::scalar::ScalarValue;
+use futures::{StreamExt, TryStreamExt};
+use std::any::Any;
+use std::sync::Arc;
+use tokio_postgres::types::{Kind, Type};
+use tokio_postgres::{Client, NoTls, Row};
+
+const BATCH_SIZE: usize = 8192;
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// How a postgres column is fetched and decoded into arrow
+#[derive(Debug, Clone, Copy)]
+enum PgColumn {
+    Bool,
+    Int2,
+    Int4,
+    Int8,
+    Float4,
+    Float8,
+    /// A numeric with a precision and scale that fit a decimal128
+    Decimal(u8, i8),
+    Numeric,
+    Text,
+    Bytea,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Int2Array,
+    Int4Array,
+    Int8Array,
+    Float4Array,
+    Float8Array,
+    TextArray,
+}
+
+/// A postgres table exposed to DataFusion, projection, filters and limit are pushed
+/// down to the generated `SELECT` statement and rows are streamed back in batches.
+#[derive(Debug)]
+pub struct PostgresTable {
+    client: Arc<Client>,
+    select: PgSelect,
+}
+
+/// The columns of a postgres table and how DataFusion scans are written as SQL against it
+#[derive(Debug)]
+struct PgSelect {
+    table: String,
+    schema: SchemaRef,
+    columns: Vec<PgColumn>,
+}
+
+impl PostgresTable {
+    pub async fn try_new(conn: &str, table: &str) -> anyhow::Result<Self> {
+        let (client, connection) = tokio_postgres::connect(conn, NoTls).await?;
+        // once the connection fails the queries of the client return the error
+        tokio::spawn(connection);
+
+        let table = quote_table(table);
+        let stmt = client
+            .prepare(&format!("SELECT * FROM {} LIMIT 0", table))
+            .await?;
+        let (fields, columns): (Vec<_>, Vec<_>) = stmt
+            .columns()
+            .iter()
+            .map(|c| {
+                let column = pg_column(c.type_(), c.type_modifier());
+                (Field::new(c.name(), arrow_type(column), true), column)
+            })
+            .unzip();
+
+        Ok(Self {
+            client: Arc::new(client),
+            select: PgSelect {
+                table,
+                schema: Arc::new(Schema::new(fields)),
+                columns,
+            },
+        })
+    }
+}
+
+impl PgSelect {
+    fn select_sql(
+        &self,
+        projection: &[usize],
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> anyhow::Result<String> {
+        let fields = self.schema.fields();
+        let select = if projection.is_empty() {
+            "NULL".to_string()
+        } else {
+            projection
+                .iter()
+                .map(|&i| {
+                    let name = quote_ident(fields[i].name());
+                    match self.columns[i] {
+                        PgColumn::Numeric => format!("{}::float8", name),
+                        PgColumn::Decimal(..) | PgColumn::Text => format!("{}::text", name),
+                        PgColumn::TextArray => format!("{}::text[]", name),
+                        _ => name,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut sql = format!("SELECT {} FROM {}", select, self.table);
+        if !filters.is_empty() {
+            let predicates = filters
+                .iter()
+                .map(|f| {
+                    self.filter_to_sql(f)
+                        .ok_or_else(|| anyhow!("unsupported filter: {}", f))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            sql.push_str(" WHERE ");
+            sql.push_str(&predicates.join(" AND "));
+        }
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        Ok(sql)
+    }
+
+    /// Translate a DataFusion filter into a postgres predicate, `None` if it can't be pushed down
+    fn filter_to_sql(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Column(c) => {
+                let idx = self.schema.index_of(&c.name).ok()?;
+                let name = quote_ident(&c.name);
+                // compare with the same representation DataFusion sees
+                match self.columns[idx] {
+                    PgColumn::Numeric => Some(format!("{}::float8", name)),
+                    PgColumn::Text => Some(format!("{}::text", name)),
+                    PgColumn::Decimal(..) => Some(name),
+                    PgColumn::TextArray => None,
+                    _ => Some(name),
+                }
+            }
+            Expr::Literal(v) => literal_to_sql(v),
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                let ordering = matches!(
+                    op,
+                    Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq
+                );
+                let op = match op {
+                    Operator::Eq => "=",
+                    Operator::NotEq => "<>",
+                    Operator::Lt => "<",
+                    Operator::LtEq => "<=",
+                    Operator::Gt => ">",
+                    Operator::GtEq => ">=",
+                    Operator::And => "AND",
+                    Operator::Or => "OR",
+                    Operator::Plus => "+",
+                    Operator::Minus => "-",
+                    Operator::Multiply => "*",
+                    Operator::Divide => "/",
+                    Operator::Modulo => "%",
+                    _ => return None,
+                };
+                let (mut left_sql, mut right_sql) =
+                    (self.filter_to_sql(left)?, self.filter_to_sql(right)?);
+                // text ordering must follow byte order like DataFusion, not the database collation
+                if ordering && is_string_literal(right) {
+                    right_sql.push_str(" COLLATE \"C\"");
+                } else if ordering && is_string_literal(left) {
+                    left_sql.push_str(" COLLATE \"C\"");
+                }
+                Some(format!("({} {} {})", left_sql, op, right_sql))
+            }
+            Expr::Not(e) => Some(format!("(NOT {})", self.filter_to_sql(e)?)),
+            Expr::Negative(e) => Some(format!("(-{})", self.filter_to_sql(e)?)),
+            Expr::IsNull(e) => Some(format!("({} IS NULL)", self.filter_to_sql(e)?)),
+            Expr::IsNotNull(e) => Some(format!("({} IS NOT NULL)", self.filter_to_sql(e)?)),
+            Expr::Between(Between {
+                expr,
+                negated,
+                low,
+                high,
+            }) => Some(format!(
+                "({} {}BETWEEN {} AND {})",
+                self.filter_to_sql(expr)?,
+                if *negated { "NOT " } else { "" },
+                self.filter_to_sql(low)?,
+                self.filter_to_sql(high)?
+            )),
+            Expr::InList(list) => {
+                let values = list
+                    .list
+                    .iter()
+                    .map(|e| self.filter_to_sql(e))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!(
+                    "({} {}IN ({}))",
+                    self.filter_to_sql(&list.expr)?,
+                    if list.negated { "NOT " } else { "" },
+                    values.join(", ")
+                ))
+            }
+            Expr::Like(Like {
+                negated,
+                expr,
+                pattern,
+                escape_char: None,
+                case_insensitive,
+            }) => Some(format!(
+                "({} {}{} {})",
+                self.filter_to_sql(expr)?,
+                if *negated { "NOT " } else { "" },
+                if *case_insensitive { "ILIKE" } else { "LIKE" },
+                self.filter_to_sql(pattern)?
+            )),
+            _ => None,
+        }
+    }
+
+    /// Filters that translate are applied by postgres, only comparisons of columns with
+    /// literals are left to it alone. Arithmetic and patterns are checked again by DataFusion
+    /// since postgres evaluates them differently, e.g. with integer division or its collation
+    fn filters_pushdown(&self, filters: &[&Expr]) -> Vec<TableProviderFilterPushDown> {
+        filters
+            .iter()
+            .map(|f| match self.filter_to_sql(f) {
+                Some(_) if is_comparison(f) => TableProviderFilterPushDown::Exact,
+                Some(_) => TableProviderFilterPushDown::Inexact,
+                None => TableProviderFilterPushDown::Unsupported,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TableProvider for PostgresTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.select.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let projection = projection
+            .cloned()
+            .unwrap_or_else(|| (0..self.select.columns.len()).collect());
+        let sql = self
+            .select
+            .select_sql(&projection, filters, limit)
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+        let schema = Arc::new(self.select.schema.project(&projection)?);
+        let columns = projection.iter().map(|&i| self.select.columns[i]).collect();
+
+        let partition = PostgresStream {
+            client: self.client.clone(),
+            sql,
+            schema: schema.clone(),
+            columns,
+        };
+        let exec =
+            StreamingTableExec::try_new(schema, vec![Arc::new(partition)], None, [], false, None)?;
+        Ok(Arc::new(exec))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(self.select.filters_pushdown(filters))
+    }
+}
+
+#[derive(Debug)]
+struct PostgresStream {
+    client: Arc<Client>,
+    sql: String,
+    schema: SchemaRef,
+    columns: Vec<PgColumn>,
+}
+
+impl PartitionStream for PostgresStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let client = self.client.clone();
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+        let columns = self.columns.clone();
+
+        let rows =
+            futures::stream::once(
+                async move { client.query_raw(&sql, Vec::<String>::new()).await },
+            )
+            .try_flatten()
+            .map_err(|e| DataFusionError::External(Box::new(e)));
+        let batches = rows.chunks(BATCH_SIZE).map(move |rows| {
+            let rows = rows.into_iter().collect::<Result<Vec<_>>>()?;
+            rows_to_batch(&schema, &columns, &rows)
+        });
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), batches))
+    }
+}
+
+fn pg_column(ty: &Type, type_modifier: i32) -> PgColumn {
+    match *ty {
+        Type::BOOL => PgColumn::Bool,
+        Type::INT2 => PgColumn::Int2,
+        Type::INT4 => PgColumn::Int4,
+        Type::INT8 => PgColumn::Int8,
+        Type::FLOAT4 => PgColumn::Float4,
+        Type::FLOAT8 => PgColumn::Float8,
+        Type::NUMERIC => numeric_column(type_modifier),
+        Type::BYTEA => PgColumn::Bytea,
+        Type::DATE => PgColumn::Date,
+        Type::TIME => PgColumn::Time,
+        Type::TIMESTAMP => PgColumn::Timestamp,
+        Type::TIMESTAMPTZ => PgColumn::TimestampTz,
+        Type::INT2_ARRAY => PgColumn::Int2Array,
+        Type::INT4_ARRAY => PgColumn::Int4Array,
+        Type::INT8_ARRAY => PgColumn::Int8Array,
+        Type::FLOAT4_ARRAY => PgColumn::Float4Array,
+        Type::FLOAT8_ARRAY => PgColumn::Float8Array,
+        // everything else (varchar, enums, json, uuid...) is read through its text form
+        _ => match ty.kind() {
+            Kind::Array(_) => PgColumn::TextArray,
+            _ => PgColumn::Text,
+        },
+    }
+}
+
+/// A `numeric(p, s)` is read as a decimal if it fits one, the precision and scale are packed
+/// in the type modifier, which is -1 for a numeric without them
+fn numeric_column(type_modifier: i32) -> PgColumn {
+    if type_modifier < 4 {
+        return PgColumn::Numeric;
+    }
+    let precision = ((type_modifier - 4) >> 16) & 0xffff;
+    let scale = (type_modifier - 4) & 0xffff;
+    match precision <= DECIMAL128_MAX_PRECISION as i32 && scale <= precision {
+        true => PgColumn::Decimal(precision as u8, scale as i8),
+        false => PgColumn::Numeric,
+    }
+}
+
+fn arrow_type(column: PgColumn) -> DataType {
+    let list = |dt| DataType::List(Arc::new(Field::new_list_field(dt, true)));
+    match column {
+        PgColumn::Bool => DataType::Boolean,
+        PgColumn::Int2 => DataType::Int16,
+        PgColumn::Int4 => DataType::Int32,
+        PgColumn::Int8 => DataType::Int64,
+        PgColumn::Float4 => DataType::Float32,
+        PgColumn::Float8 | PgColumn::Numeric => DataType::Float64,
+        PgColumn::Decimal(precision, scale) => DataType::Decimal128(precision, scale),
+        PgColumn::Text => DataType::Utf8,
+        PgColumn::Bytea => DataType::Binary,
+        PgColumn::Date => DataType::Date32,
+        PgColumn::Time => DataType::Time64(TimeUnit::Microsecond),
+        PgColumn::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+        PgColumn::TimestampTz => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        PgColumn::Int2Array => list(DataType::Int16),
+        PgColumn::Int4Array => list(DataType::Int32),
+        PgColumn::Int8Array => list(DataType::Int64),
+        PgColumn::Float4Array => list(DataType::Float32),
+        PgColumn::Float8Array => list(DataType::Float64),
+        PgColumn::TextArray => list(DataType::Utf8),
+    }
+}
+
+macro_rules! column_values {
+    ($rows:expr, $idx:expr, $ty:ty) => {
+        $rows
+            .iter()
+            .map(|r| r.try_get::<_, Option<$ty>>($idx))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?
+    };
+}
+
+macro_rules! list_array {
+    ($rows:expr, $idx:expr, $ty:ty, $arrow:ty) => {
+        Arc::new(ListArray::from_iter_primitive::<$arrow, _, _>(
+            column_values!($rows, $idx, Vec<Option<$ty>>),
+        ))
+    };
+}
+
+fn rows_to_batch(schema: &SchemaRef, columns: &[PgColumn], rows: &[Row]) -> Result<RecordBatch> {
+    let arrays = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let array: ArrayRef = match column {
+                PgColumn::Bool => Arc::new(BooleanArray::from(column_values!(rows, i, bool))),
+                PgColumn::Int2 => Arc::new(Int16Array::from(column_values!(rows, i, i16))),
+                PgColumn::Int4 => Arc::new(Int32Array::from(column_values!(rows, i, i32))),
+                PgColumn::Int8 => Arc::new(Int64Array::from(column_values!(rows, i, i64))),
+                PgColumn::Float4 => Arc::new(Float32Array::from(column_values!(rows, i, f32))),
+                PgColumn::Float8 | PgColumn::Numeric => {
+                    Arc::new(Float64Array::from(column_values!(rows, i, f64)))
+                }
+                PgColumn::Text => Arc::new(StringArray::from(column_values!(rows, i, String))),
+                PgColumn::Decimal(precision, scale) => {
+                    let values = StringArray::from(column_values!(rows, i, String));
+                    cast(&values, &DataType::Decimal128(*precision, *scale))?
+                }
+                PgColumn::Bytea => {
+                    let values = column_values!(rows, i, Vec<u8>);
+                    Arc::new(BinaryArray::from_iter(values))
+                }
+                PgColumn::Date => {
+                    let values = column_values!(rows, i, NaiveDate);
+                    Arc::new(Date32Array::from_iter(
+                        values
+                            .into_iter()
+                            .map(|v| v.map(|d| d.num_days_from_ce() - EPOCH_DAYS_FROM_CE)),
+                    ))
+                }
+                PgColumn::Time => {
+                    let values = column_values!(rows, i, NaiveTime);
+                    Arc::new(Time64MicrosecondArray::from_iter(values.into_iter().map(
+                        |v| {
+                            v.map(|t| {
+                                t.num_seconds_from_midnight() as i64 * 1_000_000
+                                    + t.nanosecond() as i64 / 1_000
+                            })
+                        },
+                    )))
+                }
+                PgColumn::Timestamp => {
+                    let values = column_values!(rows, i, NaiveDateTime);
+                    Arc::new(TimestampMicrosecondArray::from_iter(
+                        values
+                            .into_iter()
+                            .map(|v| v.map(|t| t.and_utc().timestamp_micros())),
+                    ))
+                }
+                PgColumn::TimestampTz => {
+                    let values = column_values!(rows, i, DateTime<Utc>);
+                    Arc::new(
+                        TimestampMicrosecondArray::from_iter(
+                            values.into_iter().map(|v| v.map(|t| t.timestamp_micros())),
+                        )
+                        .with_timezone("UTC"),
+                    )
+                }
+                PgColumn::Int2Array => list_array!(rows, i, i16, Int16Type),
+                PgColumn::Int4Array => list_array!(rows, i, i32, Int32Type),
+                PgColumn::Int8Array => list_array!(rows, i, i64, Int64Type),
+                PgColumn::Float4Array => list_array!(rows, i, f32, Float32Type),
+                PgColumn::Float8Array => list_array!(rows, i, f64, Float64Type),
+                PgColumn::TextArray => {
+                    let mut builder = ListBuilder::new(StringBuilder::new());
+                    for value in column_values!(rows, i, Vec<Option<String>>) {
+                        builder.append_option(value);
+                    }
+                    Arc::new(builder.finish())
+                }
+            };
+            Ok(array)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // count(*) style queries project no column at all, so the row count is set explicitly
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        arrays,
+        &options,
+    )?)
+}
+
+/// A comparison of a column with literals, or a conjunction, disjunction or negation of them
+fn is_comparison(expr: &Expr) -> bool {
+    let is_column = |e: &Expr| matches!(e, Expr::Column(_));
+    let is_literal = |e: &Expr| matches!(e, Expr::Literal(_));
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            Operator::And | Operator::Or => is_comparison(left) && is_comparison(right),
+            Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq => {
+                (is_column(left) && is_literal(right)) || (is_literal(left) && is_column(right))
+            }
+            _ => false,
+        },
+        Expr::Not(e) => is_comparison(e),
+        Expr::IsNull(e) | Expr::IsNotNull(e) => is_column(e),
+        Expr::Between(between) => {
+            is_column(&between.expr) && is_literal(&between.low) && is_literal(&between.high)
+        }
+        Expr::InList(list) => is_column(&list.expr) && list.list.iter().all(is_literal),
+        _ => false,
+    }
+}
+
+fn is_string_literal(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Literal(ScalarValue::Utf8(_) | ScalarValue::LargeUtf8(_) | ScalarValue::Utf8View(_))
+    )
+}
+
+fn literal_to_sql(v: &ScalarValue) -> Option<String> {
+    if v.is_null() {
+        return Some("NULL".to_string());
+    }
+    let s = match v {
+        ScalarValue::Boolean(Some(b)) => b.to_string(),
+        ScalarValue::Int8(Some(i)) => i.to_string(),
+        ScalarValue::Int16(Some(i)) => i.to_string(),
+        ScalarValue::Int32(Some(i)) => i.to_string(),
+        ScalarValue::Int64(Some(i)) => i.to_string(),
+        ScalarValue::UInt8(Some(i)) => i.to_string(),
+        ScalarValue::UInt16(Some(i)) => i.to_string(),
+        ScalarValue::UInt32(Some(i)) => i.to_string(),
+        ScalarValue::UInt64(Some(i)) => i.to_string(),
+        ScalarValue::Float32(Some(f)) if f.is_finite() => format!("{}::float4", f),
+        ScalarValue::Float64(Some(f)) if f.is_finite() => format!("{}::float8", f),
+        ScalarValue::Decimal128(Some(v), precision, scale) => format!(
+            "{}::numeric",
+            Decimal128Type::format_decimal(*v, *precision, *scale)
+        ),
+        ScalarValue::Utf8(Some(s))
+        | ScalarValue::LargeUtf8(Some(s))
+        | ScalarValue::Utf8View(Some(s)) => quote_literal(s),
+        ScalarValue::Date32(Some(d)) => {
+            let date = NaiveDate::from_num_days_from_ce_opt(*d + EPOCH_DAYS_FROM_CE)?;
+            format!("'{}'::date", date)
+        }
+        ScalarValue::TimestampSecond(Some(t), tz) => timestamp_to_sql(t * 1_000_000, tz)?,
+        ScalarValue::TimestampMillisecond(Some(t), tz) => timestamp_to_sql(t * 1_000, tz)?,
+        ScalarValue::TimestampMicrosecond(Some(t), tz) => timestamp_to_sql(*t, tz)?,
+        ScalarValue::TimestampNanosecond(Some(t), tz) => timestamp_to_sql(t / 1_000, tz)?,
+        _ => return None,
+    };
+    Some(s)
+}
+
+fn timestamp_to_sql(micros: i64, tz: &Option<Arc<str>>) -> Option<String> {
+    let t = DateTime::from_timestamp_micros(micros)?;
+    Some(match tz {
+        Some(_) => format!("'{}'::timestamptz", t.format("%Y-%m-%d %H:%M:%S%.6f+00")),
+        None => format!("'{}'::timestamp", t.format("%Y-%m-%d %H:%M:%S%.6f")),
+    })
+}
+
+fn quote_ident(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// `schema.table` or `table`, every part is quoted so mixed case names are kept
+fn quote_table(s: &str) -> String {
+    s.split('.').map(quote_ident).collect::<Vec<_>>().join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::functions::expr_fn::lower;
+    use datafusion::prelude::{col, lit, SessionContext};
+
+    fn select() -> PgSelect {
+        let columns = [
+            ("id", Type::INT8),
+            ("name", Type::VARCHAR),
+            ("price", Type::NUMERIC),
+            ("tags", Type::TEXT_ARRAY),
+            ("at", Type::TIMESTAMPTZ),
+        ]
+        .map(|(name, ty)| (name, pg_column(&ty, -1)));
+        let fields = columns
+            .iter()
+            .map(|(name, column)| Field::new(*name, arrow_type(*column), true))
+            .collect::<Vec<_>>();
+        PgSelect {
+            table: quote_table("public.Items"),
+            schema: Arc::new(Schema::new(fields)),
+            columns: columns.iter().map(|(_, column)| *column).collect(),
+        }
+    }
+
+    #[test]
+    fn postgres_types_should_map_to_arrow() {
+        let list = |dt| DataType::List(Arc::new(Field::new_list_field(dt, true)));
+        let types = [
+            (Type::BOOL, DataType::Boolean),
+            (Type::INT2, DataType::Int16),
+            (Type::INT4, DataType::Int32),
+            (Type::INT8, DataType::Int64),
+            (Type::FLOAT4, DataType::Float32),
+            (Type::NUMERIC, DataType::Float64),
+            (Type::VARCHAR, DataType::Utf8),
+            (Type::UUID, DataType::Utf8),
+            (Type::JSONB, DataType::Utf8),
+            (Type::BYTEA, DataType::Binary),
+            (Type::DATE, DataType::Date32),
+            (Type::TIME, DataType::Time64(TimeUnit::Microsecond)),
+            (
+                Type::TIMESTAMP,
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+            ),
+            (
+                Type::TIMESTAMPTZ,
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            ),
+            (Type::INT4_ARRAY, list(DataType::Int32)),
+            (Type::FLOAT8_ARRAY, list(DataType::Float64)),
+            (Type::VARCHAR_ARRAY, list(DataType::Utf8)),
+            (Type::UUID_ARRAY, list(DataType::Utf8)),
+        ];
+        for (ty, data_type) in types {
+            assert_eq!(arrow_type(pg_column(&ty, -1)), data_type, "{}", ty);
+        }
+
+        // numeric(p, s) has a type modifier of (p << 16 | s) + 4
+        let numeric = |precision: i32, scale: i32| {
+            arrow_type(pg_column(&Type::NUMERIC, (precision << 16 | scale) + 4))
+        };
+        assert_eq!(numeric(10, 2), DataType::Decimal128(10, 2));
+        assert_eq!(numeric(38, 0), DataType::Decimal128(38, 0));
+        assert_eq!(numeric(39, 2), DataType::Float64);
+    }
+
+    #[test]
+    fn postgres_select_should_push_down_filters() {
+        let select = select();
+        let filters = [
+            col("id").gt(lit(1)).and(col("name").not_eq(lit("it's"))),
+            col("name").lt(lit("m")),
+            col("price").between(lit(1.5), lit(2.0)),
+            col("name").in_list(vec![lit("a"), lit("b")], true),
+            col("at").is_null(),
+            col("name").like(lit("a%")),
+            (col("id") / lit(2)).eq(lit(1)),
+        ];
+        let sql = select.select_sql(&[0, 1], &filters, Some(10)).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"id\", \"name\"::text FROM \"public\".\"Items\" \
+             WHERE ((\"id\" > 1) AND (\"name\"::text <> 'it''s')) \
+             AND (\"name\"::text < 'm' COLLATE \"C\") \
+             AND (\"price\"::float8 BETWEEN 1.5::float8 AND 2::float8) \
+             AND (\"name\"::text NOT IN ('a', 'b')) \
+             AND (\"at\" IS NULL) \
+             AND (\"name\"::text LIKE 'a%') \
+             AND ((\"id\" / 2) = 1) LIMIT 10"
+        );
+        // patterns and arithmetic are checked again by DataFusion
+        let pushdown = select.filters_pushdown(&filters.iter().collect::<Vec<_>>());
+        let exact = pushdown
+            .iter()
+            .map(|p| matches!(p, TableProviderFilterPushDown::Exact))
+            .collect::<Vec<_>>();
+        assert_eq!(exact, [true, true, true, true, true, false, false]);
+        assert!(matches!(pushdown[6], TableProviderFilterPushDown::Inexact));
+
+        // functions and array columns are left to DataFusion
+        let filters = [
+            lower(col("name")).eq(lit("a")),
+            col("tags").is_not_null(),
+            col("id").gt(lit(1)).or(col("tags").is_null()),
+        ];
+        let pushdown = select.filters_pushdown(&filters.iter().collect::<Vec<_>>());
+        assert!(pushdown
+            .iter()
+            .all(|p| matches!(p, TableProviderFilterPushDown::Unsupported)));
+        assert!(select.select_sql(&[0], &filters, None).is_err());
+        assert_eq!(
+            select.select_sql(&[], &[], None).unwrap(),
+            "SELECT NULL FROM \"public\".\"Items\""
+        );
+    }
+
+    /// Read a table of the database at `DATA_FORGE_TEST_POSTGRES`, e.g.
+    /// `postgresql://postgres@localhost/postgres`, the test is skipped without it
+    #[tokio::test]
+    async fn postgres_table_should_read_rows_with_pushed_down_filters() {
+        let Ok(conn) = std::env::var("DATA_FORGE_TEST_POSTGRES") else {
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&conn, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS data_forge_items;
+                 CREATE TABLE data_forge_items (
+                     id int8, name varchar, price numeric(10, 2), ratio numeric, tags text[]
+                 );
+                 INSERT INTO data_forge_items VALUES
+                     (1, 'apple', 1.25, 0.5, '{red,green}'),
+                     (2, 'Banana', 12345678.99, NULL, NULL),
+                     (3, NULL, NULL, 1.5, '{}');",
+            )
+            .await
+            .unwrap();
+
+        let table = PostgresTable::try_new(&conn, "data_forge_items")
+            .await
+            .unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table("items", Arc::new(table)).unwrap();
+        let df = ctx
+            .sql("SELECT id, name, price, ratio, tags FROM items WHERE price > 1.5 OR id / 2 = 1")
+            .await
+            .unwrap();
+        let batches = df.collect().await.unwrap();
+        let expected = [
+            "+----+--------+-------------+-------+------+",
+            "| id | name   | price       | ratio | tags |",
+            "+----+--------+-------------+-------+------+",
+            "| 2  | Banana | 12345678.99 |       |      |",
+            "| 3  |        |             | 1.5   | []   |",
+            "+----+--------+-------------+-------+------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+        let count = ctx
+            .sql("SELECT count(*) AS n FROM items WHERE name LIKE '%a%'")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            pretty_format_batches(&count).unwrap().to_string(),
+            ["+---+", "| n |", "+---+", "| 2 |", "+---+"].join("\n")
+        );
+        client
+            .batch_execute("DROP TABLE data_forge_items")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use crate::backend::sqlite::SqliteTable;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::{PartitionStream, StreamingTableExec};
+use datafusion::physical_plan::ExecutionPlan;
+use futures::channel::mpsc;
+use futures::SinkExt;
+use std::any::Any;
+use std::sync::Arc;
+
+/// A sqlite table exposed to DataFusion, projection and limit are pushed down to the
+/// generated `SELECT` statement which runs on its own thread.
+#[derive(Debug)]
+pub struct SqliteProvider(SqliteTable);
+
+impl SqliteProvider {
+    pub fn try_new(path: &str, table: &str) -> anyhow::Result<Self> {
+        Ok(Self(SqliteTable::try_new(path, table)?))
+    }
+}
+
+#[async_trait]
+impl TableProvider for SqliteProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.0.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let projection = projection
+            .cloned()
+            .unwrap_or_else(|| (0..self.0.schema().fields().len()).collect());
+        let schema = Arc::new(self.0.schema().project(&projection)?);
+        let partition = SqliteStream {
+            table: self.0.clone(),
+            projection,
+            limit,
+            schema: schema.clone(),
+        };
+        let exec =
+            StreamingTableExec::try_new(schema, vec![Arc::new(partition)], None, [], false, None)?;
+        Ok(Arc::new(exec))
+    }
+}
+
+#[derive(Debug)]
+struct SqliteStream {
+    table: SqliteTable,
+    projection: Vec<usize>,
+    limit: Option<usize>,
+    schema: SchemaRef,
+}
+
+impl PartitionStream for SqliteStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let (mut tx, rx) = mpsc::channel(2);
+        let table = self.table.clone();
+        let projection = self.projection.clone();
+        let limit = self.limit;
+        std::thread::spawn(move || {
+            // reading stops as soon as the receiver is dropped
+            let ret = table.read(&projection, limit, |batch| {
+                futures::executor::block_on(tx.send(Ok(batch))).is_ok()
+            });
+            if let Err(e) = ret {
+                let e = DataFusionError::External(e.into());
+                let _ = futures::executor::block_on(tx.send(Err(e)));
+            }
+        });
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::df::DataFusionBackend;
+    use crate::backend::sqlite::tests::shop;
+    use crate::cli::connect::DataSetConn;
+    use crate::cli::{ConnectOpts, SqlOpts};
+    use crate::{Backend, ReplDisplay};
+
+    async fn query(backend: &DataFusionBackend, sql: &str) -> anyhow::Result<String> {
+        backend
+            .sql(SqlOpts::new(sql.to_string()))
+            .await?
+            .display()
+            .await
+    }
+
+    #[tokio::test]
+    async fn connect_should_register_the_table_given_with_t() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let conn = DataSetConn::Sqlite(shop(dir.path())?);
+        let mut backend = DataFusionBackend::new();
+        let opts = ConnectOpts::new(conn, Some("orders".to_string()), "orders".to_string());
+        backend.connect(&opts).await?;
+
+        let expected = [
+            "+----+------+-----+",
+            "| id | item | qty |",
+            "+----+------+-----+",
+            "| 1  | 1    | 2   |",
+            "| 2  | 3    | 1.5 |",
+            "+----+------+-----+",
+        ];
+        let out = query(&backend, "SELECT * FROM orders ORDER BY id").await?;
+        assert_eq!(out, expected.join("\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_should_register_every_table_without_t() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let conn = DataSetConn::Sqlite(shop(dir.path())?);
+        let mut backend = DataFusionBackend::new();
+        backend
+            .connect(&ConnectOpts::new(conn, None, "shop".to_string()))
+            .await?;
+
+        let sql = "SELECT o.id, i.name FROM shop.orders o JOIN shop.items i ON o.item = i.id \
+                   ORDER BY o.id LIMIT 1";
+        let expected = [
+            "+----+------+",
+            "| id | name |",
+            "+----+------+",
+            "| 1  | pen  |",
+            "+----+------+",
+        ];
+        assert_eq!(query(&backend, sql).await?, expected.join("\n"));
+        Ok(())
+    }
+}
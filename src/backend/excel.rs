@@ -0,0 +1,479 @@
+use crate::cli::connect::ExcelOpts;
+use anyhow::anyhow;
+use calamine::{open_workbook_auto, Data, DataType as _, Reader};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use datafusion::arrow::array::{
+    ArrayRef, BooleanArray, Date32Array, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray,
+};
+use datafusion::arrow::datatypes::{Field, Schema};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// The type of a column, widened as cells of other types are seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Empty,
+    Int,
+    Float,
+    Bool,
+    Date,
+    DateTime,
+    Text,
+}
+
+/// Read a worksheet, the first row of the sheet or of the range is the header and
+/// column types are inferred from the cell types of the other rows
+pub fn read_excel(path: &str, opts: &ExcelOpts) -> anyhow::Result<RecordBatch> {
+    let mut workbook = open_workbook_auto(path)?;
+    let sheets = workbook.sheet_names();
+    let sheet = match &opts.sheet {
+        Some(sheet) if !sheets.contains(sheet) => {
+            return Err(anyhow!(
+                "sheet {} not found, available sheets: {}",
+                sheet,
+                sheets.join(", ")
+            ))
+        }
+        Some(sheet) => sheet.clone(),
+        None => sheets
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("no sheet found in {}", path))?,
+    };
+    let mut range = workbook.worksheet_range(&sheet)?;
+    if let Some(cells) = &opts.range {
+        let end = cells.end.or(range.end()).unwrap_or(cells.start);
+        range = range.range(cells.start, end);
+    }
+
+    let mut rows = range.rows().filter(|row| row.iter().any(|c| !is_empty(c)));
+    // a table needs at least one column, there's none without a header
+    let header = rows.next().ok_or_else(|| match &opts.range {
+        Some(_) => anyhow!("the range of sheet {} of {} is empty", sheet, path),
+        None => anyhow!("sheet {} of {} is empty", sheet, path),
+    })?;
+    let rows = rows.collect::<Vec<_>>();
+    let width = rows
+        .iter()
+        .map(|r| r.len())
+        .chain([header.len()])
+        .max()
+        .unwrap_or_default();
+
+    let names = column_names(header, width);
+    let (fields, columns): (Vec<_>, Vec<_>) = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let cells = rows
+                .iter()
+                .map(|r| r.get(i).unwrap_or(&Data::Empty))
+                .collect::<Vec<_>>();
+            let kind = cells
+                .iter()
+                .map(|c| cell_kind(c))
+                .fold(CellKind::Empty, widen);
+            let array = to_array(kind, &cells);
+            (Field::new(name, array.data_type().clone(), true), array)
+        })
+        .unzip();
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+/// Header cells as column names, empty ones are named by position and duplicates numbered
+fn column_names(header: &[Data], width: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    (0..width)
+        .map(|i| {
+            let name = match header.get(i) {
+                Some(c) if !is_empty(c) => c.to_string().trim().to_string(),
+                _ => format!("column_{}", i + 1),
+            };
+            let mut unique = name.clone();
+            let mut n = 1;
+            while !seen.insert(unique.clone()) {
+                n += 1;
+                unique = format!("{}_{}", name, n);
+            }
+            unique
+        })
+        .collect()
+}
+
+fn is_empty(cell: &Data) -> bool {
+    match cell {
+        Data::Empty | Data::Error(_) => true,
+        Data::String(s) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn cell_kind(cell: &Data) -> CellKind {
+    match cell {
+        c if is_empty(c) => CellKind::Empty,
+        Data::Int(_) => CellKind::Int,
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => CellKind::Int,
+        Data::Float(_) => CellKind::Float,
+        Data::Bool(_) => CellKind::Bool,
+        Data::DateTime(dt) if dt.is_datetime() => match cell.as_datetime() {
+            Some(dt) if dt.time() == NaiveTime::MIN => CellKind::Date,
+            Some(_) => CellKind::DateTime,
+            None => CellKind::Text,
+        },
+        Data::DateTimeIso(s) if NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() => CellKind::Date,
+        Data::DateTimeIso(_) if cell.as_datetime().is_some() => CellKind::DateTime,
+        _ => CellKind::Text,
+    }
+}
+
+fn present<'a>(cell: &&'a Data) -> Option<&'a Data> {
+    (!is_empty(cell)).then_some(*cell)
+}
+
+fn widen(a: CellKind, b: CellKind) -> CellKind {
+    use CellKind::*;
+    match (a, b) {
+        (a, b) if a == b => a,
+        (Empty, k) | (k, Empty) => k,
+        (Int, Float) | (Float, Int) => Float,
+        (Date, DateTime) | (DateTime, Date) => DateTime,
+        _ => Text,
+    }
+}
+
+fn to_array(kind: CellKind, cells: &[&Data]) -> ArrayRef {
+    match kind {
+        CellKind::Int => Arc::new(Int64Array::from_iter(
+            cells.iter().map(|c| present(c).and_then(|c| c.as_i64())),
+        )),
+        CellKind::Float => Arc::new(Float64Array::from_iter(
+            cells.iter().map(|c| present(c).and_then(|c| c.as_f64())),
+        )),
+        CellKind::Bool => Arc::new(BooleanArray::from_iter(
+            cells.iter().map(|c| present(c).and_then(|c| c.get_bool())),
+        )),
+        CellKind::Date => Arc::new(Date32Array::from_iter(cells.iter().map(|c| {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).expect("valid date");
+            present(c)
+                .and_then(|c| c.as_date())
+                .map(|d| (d - epoch).num_days() as i32)
+        }))),
+        CellKind::DateTime => Arc::new(TimestampMillisecondArray::from_iter(cells.iter().map(
+            |c| {
+                present(c)
+                    .and_then(|c| c.as_datetime())
+                    .map(|dt: NaiveDateTime| dt.and_utc().timestamp_millis())
+            },
+        ))),
+        CellKind::Text | CellKind::Empty => Arc::new(StringArray::from_iter(
+            cells.iter().map(|c| present(c).map(|c| c.to_string())),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::connect::CellRange;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    const MAIN: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+    const RELS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+    /// Days since the epoch of excel, which counts 1900 as a leap year
+    fn serial(dt: NaiveDateTime) -> f64 {
+        let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)
+            .unwrap()
+            .and_time(NaiveTime::MIN);
+        (dt - epoch).num_seconds() as f64 / 86400.0
+    }
+
+    /// Numbers, `TRUE` and `FALSE`, and dates and times as serials styled with the date or
+    /// date time format of the workbook, anything else is an inline string
+    fn cell(cell: &str, value: &str) -> String {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            let serial = serial(date.and_time(NaiveTime::MIN));
+            return format!(r#"<c r="{}" s="1"><v>{}</v></c>"#, cell, serial);
+        }
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+            return format!(r#"<c r="{}" s="2"><v>{}</v></c>"#, cell, serial(dt));
+        }
+        match value {
+            "TRUE" | "FALSE" => {
+                let value = u8::from(value == "TRUE");
+                return format!(r#"<c r="{}" t="b"><v>{}</v></c>"#, cell, value);
+            }
+            _ => {}
+        }
+        match value.parse::<f64>() {
+            Ok(_) => format!(r#"<c r="{}"><v>{}</v></c>"#, cell, value),
+            Err(_) => format!(
+                r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                cell, value
+            ),
+        }
+    }
+
+    /// A workbook of the given sheets, rows of cells from the first column on
+    fn workbook(path: &std::path::Path, sheets: &[(&str, &[&[&str]])]) {
+        let mut entries = vec![(
+            "_rels/.rels".to_string(),
+            format!(
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="{}/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+                RELS
+            ),
+        )];
+        let mut names = String::new();
+        let mut rels = String::new();
+        let mut types = String::new();
+        for (i, (name, rows)) in sheets.iter().enumerate() {
+            let n = i + 1;
+            names.push_str(&format!(
+                r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+                name, n, n
+            ));
+            rels.push_str(&format!(
+                r#"<Relationship Id="rId{}" Type="{}/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+                n, RELS, n
+            ));
+            types.push_str(&format!(
+                r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+                n
+            ));
+            let rows = rows
+                .iter()
+                .enumerate()
+                .map(|(r, row)| {
+                    let cells = row
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, v)| !v.is_empty())
+                        .map(|(c, v)| cell(&format!("{}{}", (b'A' + c as u8) as char, r + 1), v))
+                        .collect::<String>();
+                    format!(r#"<row r="{}">{}</row>"#, r + 1, cells)
+                })
+                .collect::<String>();
+            entries.push((
+                format!("xl/worksheets/sheet{}.xml", n),
+                format!(
+                    r#"<worksheet xmlns="{}"><sheetData>{}</sheetData></worksheet>"#,
+                    MAIN, rows
+                ),
+            ));
+        }
+        entries.push((
+            "xl/workbook.xml".to_string(),
+            format!(
+                r#"<workbook xmlns="{}" xmlns:r="{}"><sheets>{}</sheets></workbook>"#,
+                MAIN, RELS, names
+            ),
+        ));
+        entries.push((
+            "xl/_rels/workbook.xml.rels".to_string(),
+            format!(
+                r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+                rels
+            ),
+        ));
+        // cell styles 1 and 2 are the built in date and date time formats
+        entries.push((
+            "xl/styles.xml".to_string(),
+            format!(
+                r#"<styleSheet xmlns="{}"><cellXfs count="3"><xf numFmtId="0"/><xf numFmtId="14" applyNumberFormat="1"/><xf numFmtId="22" applyNumberFormat="1"/></cellXfs></styleSheet>"#,
+                MAIN
+            ),
+        ));
+        entries.push((
+            "[Content_Types].xml".to_string(),
+            format!(
+                r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>{}</Types>"#,
+                types
+            ),
+        ));
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, xml) in entries {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn read_excel_should_name_columns_and_reject_empty_sheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.xlsx");
+        let data: &[&[&str]] = &[
+            &["id", "name", "name"],
+            &["1", "Ada", ""],
+            &["2.5", "", "x"],
+        ];
+        let blank: &[&[&str]] = &[&["", ""]];
+        workbook(&path, &[("data", data), ("empty", &[]), ("blank", blank)]);
+        let path = path.to_string_lossy().to_string();
+
+        let batch = read_excel(&path, &ExcelOpts::default()).unwrap();
+        assert_eq!(
+            pretty_format_batches(&[batch]).unwrap().to_string(),
+            "+-----+------+--------+\n\
+             | id  | name | name_2 |\n\
+             +-----+------+--------+\n\
+             | 1.0 | Ada  |        |\n\
+             | 2.5 |      | x      |\n\
+             +-----+------+--------+"
+        );
+        for sheet in ["empty", "blank"] {
+            let opts = ExcelOpts {
+                sheet: Some(sheet.to_string()),
+                ..Default::default()
+            };
+            let e = read_excel(&path, &opts).unwrap_err();
+            assert_eq!(
+                e.to_string(),
+                format!("sheet {} of {} is empty", sheet, path)
+            );
+        }
+        let opts = ExcelOpts {
+            range: Some(CellRange {
+                start: (10, 0),
+                end: Some((12, 2)),
+            }),
+            ..Default::default()
+        };
+        let e = read_excel(&path, &opts).unwrap_err();
+        assert!(e.to_string().contains("range of sheet data"), "{}", e);
+    }
+
+    fn read(path: &std::path::Path, opts: &ExcelOpts) -> String {
+        let batch = read_excel(&path.to_string_lossy(), opts).unwrap();
+        pretty_format_batches(&[batch]).unwrap().to_string()
+    }
+
+    #[test]
+    fn read_excel_should_read_only_the_given_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.xlsx");
+        let report: &[&[&str]] = &[
+            &["Sales report"],
+            &[],
+            &["", "id", "region", "total"],
+            &["", "1", "north", "10.5"],
+            &["", "2", "south", "7"],
+            &["", "", "", "", "notes"],
+        ];
+        workbook(&path, &[("report", report)]);
+
+        let opts = ExcelOpts {
+            range: Some(CellRange {
+                start: (2, 1),
+                end: Some((3, 2)),
+            }),
+            ..Default::default()
+        };
+        let expected = [
+            "+----+--------+",
+            "| id | region |",
+            "+----+--------+",
+            "| 1  | north  |",
+            "+----+--------+",
+        ];
+        assert_eq!(read(&path, &opts), expected.join("\n"));
+
+        // without an end the range goes to the last cell of the sheet, notes included
+        let opts = ExcelOpts {
+            range: Some(CellRange {
+                start: (2, 1),
+                end: None,
+            }),
+            ..Default::default()
+        };
+        let expected = [
+            "+----+--------+-------+----------+",
+            "| id | region | total | column_4 |",
+            "+----+--------+-------+----------+",
+            "| 1  | north  | 10.5  |          |",
+            "| 2  | south  | 7.0   |          |",
+            "|    |        |       | notes    |",
+            "+----+--------+-------+----------+",
+        ];
+        assert_eq!(read(&path, &opts), expected.join("\n"));
+    }
+
+    #[test]
+    fn read_excel_should_read_date_and_bool_cells_as_typed_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("orders.xlsx");
+        let orders: &[&[&str]] = &[
+            &["day", "shipped", "paid", "note"],
+            &["2024-05-01", "2024-05-02 08:30:00", "TRUE", "TRUE"],
+            &["2024-05-03", "2024-05-03", "FALSE", "late"],
+            &["", "", "", ""],
+            &["2024-05-04", "", "", ""],
+        ];
+        workbook(&path, &[("orders", orders)]);
+
+        let batch = read_excel(&path.to_string_lossy(), &ExcelOpts::default()).unwrap();
+        let types = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().to_string())
+            .collect::<Vec<_>>();
+        // a date time among dates makes a column of date times, a word among bools one of text
+        assert_eq!(
+            types,
+            ["Date32", "Timestamp(Millisecond, None)", "Boolean", "Utf8"]
+        );
+        let expected = [
+            "+------------+---------------------+-------+------+",
+            "| day        | shipped             | paid  | note |",
+            "+------------+---------------------+-------+------+",
+            "| 2024-05-01 | 2024-05-02T08:30:00 | true  | true |",
+            "| 2024-05-03 | 2024-05-03T00:00:00 | false | late |",
+            "| 2024-05-04 |                     |       |      |",
+            "+------------+---------------------+-------+------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&[batch]).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn read_excel_should_read_the_given_sheet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.xlsx");
+        let north: &[&[&str]] = &[&["id"], &["1"]];
+        let south: &[&[&str]] = &[&["id", "city"], &["2", "Rome"]];
+        workbook(&path, &[("north", north), ("south", south)]);
+
+        let opts = ExcelOpts {
+            sheet: Some("south".to_string()),
+            ..Default::default()
+        };
+        let expected = [
+            "+----+------+",
+            "| id | city |",
+            "+----+------+",
+            "| 2  | Rome |",
+            "+----+------+",
+        ];
+        assert_eq!(read(&path, &opts), expected.join("\n"));
+
+        let opts = ExcelOpts {
+            sheet: Some("west".to_string()),
+            ..Default::default()
+        };
+        let e = read_excel(&path.to_string_lossy(), &opts).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "sheet west not found, available sheets: north, south"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::backend::schema::{parse_type, split_top_level};
+use crate::cli::connect::FileOpts;
+use anyhow::anyhow;
+use datafusion::arrow::array::{Array, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 8192;
+
+/// The columns of a fixed-width file, in the order they are registered
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout(pub Vec<LayoutColumn>);
+
+/// A column taking `width` bytes from the zero based `start` of every line
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutColumn {
+    pub name: String,
+    pub start: usize,
+    pub width: usize,
+    pub data_type: DataType,
+}
+
+impl Layout {
+    pub fn schema(&self) -> SchemaRef {
+        let fields = self
+            .0
+            .iter()
+            .map(|c| Field::new(&c.name, c.data_type.clone(), true))
+            .collect::<Vec<_>>();
+        Arc::new(Schema::new(fields))
+    }
+}
+
+/// Parse a layout given inline as `name:start:width[:type],...` or as the path of a file
+/// holding one column per line, starts are one based like in copybooks and the type is a
+/// string if omitted
+pub fn parse_layout(spec: &str) -> anyhow::Result<Layout> {
+    let content = match Path::new(spec).is_file() {
+        true => std::fs::read_to_string(spec)?,
+        false => spec.to_string(),
+    };
+    let columns = content
+        .lines()
+        .flat_map(split_top_level)
+        .map(|column| {
+            let parts = column.splitn(4, ':').map(str::trim).collect::<Vec<_>>();
+            let [name, start, width, data_type @ ..] = parts.as_slice() else {
+                return Err(anyhow!(
+                    "invalid column {}, expected name:start:width[:type]",
+                    column
+                ));
+            };
+            let position = |v: &str| v.parse::<usize>().ok().filter(|v| *v > 0);
+            let (Some(start), Some(width)) = (position(start), position(width)) else {
+                return Err(anyhow!(
+                    "invalid column {}, start and width must be positive integers",
+                    column
+                ));
+            };
+            Ok(LayoutColumn {
+                name: name.to_string(),
+                start: start - 1,
+                width,
+                data_type: data_type
+                    .first()
+                    .map_or(Ok(DataType::Utf8), |t| parse_type(t))?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if columns.is_empty() {
+        return Err(anyhow!("invalid layout {}, no columns", spec));
+    }
+    Ok(Layout(columns))
+}
+
+/// Read a fixed-width file, fields are trimmed and blank ones are null. Lines shorter than the
+/// layout leave their last columns null, empty lines are skipped
+pub fn read_fixed_width(
+    file_opts: &FileOpts,
+    layout: &Layout,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let file = File::open(&file_opts.filename)?;
+    let reader = BufReader::new(file_opts.compression.convert_read(file)?);
+    let schema = layout.schema();
+    let mut batches = vec![];
+    let mut lines = vec![];
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if line.is_empty() {
+            continue;
+        }
+        lines.push((i + 1, line));
+        if lines.len() == BATCH_SIZE {
+            batches.push(to_batch(&schema, layout, &lines)?);
+            lines.clear();
+        }
+    }
+    if !lines.is_empty() {
+        batches.push(to_batch(&schema, layout, &lines)?);
+    }
+    Ok((schema, batches))
+}
+
+fn to_batch(
+    schema: &SchemaRef,
+    layout: &Layout,
+    lines: &[(usize, Vec<u8>)],
+) -> anyhow::Result<RecordBatch> {
+    let columns = layout
+        .0
+        .iter()
+        .map(|column| {
+            let values = lines
+                .iter()
+                .map(|(_, line)| field(line, column))
+                .collect::<StringArray>();
+            let array = cast(&values, &column.data_type)?;
+            // values that can't be cast are made null, the first one is reported instead
+            let invalid = (0..values.len()).find(|i| values.is_valid(*i) && array.is_null(*i));
+            if let Some(i) = invalid {
+                return Err(anyhow!(
+                    "invalid {} value {} of column {} on line {}",
+                    column.data_type,
+                    values.value(i),
+                    column.name,
+                    lines[i].0
+                ));
+            }
+            Ok(array)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+fn field(line: &[u8], column: &LayoutColumn) -> Option<String> {
+    let end = line.len().min(column.start + column.width);
+    let value = line.get(column.start..end)?;
+    let value = String::from_utf8_lossy(value);
+    Some(value.trim())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+    fn file(dir: &tempfile::TempDir, content: &str) -> FileOpts {
+        let path = dir.path().join("data.txt");
+        std::fs::write(&path, content).unwrap();
+        FileOpts::new(
+            path.to_string_lossy().to_string(),
+            "txt".to_string(),
+            FileCompressionType::UNCOMPRESSED,
+        )
+    }
+
+    #[test]
+    fn parse_layout_should_read_one_based_columns() {
+        let layout = parse_layout("id:1:3:int, name : 4 : 5").unwrap();
+        assert_eq!(
+            layout.0,
+            vec![
+                LayoutColumn {
+                    name: "id".to_string(),
+                    start: 0,
+                    width: 3,
+                    data_type: DataType::Int32,
+                },
+                LayoutColumn {
+                    name: "name".to_string(),
+                    start: 3,
+                    width: 5,
+                    data_type: DataType::Utf8,
+                },
+            ]
+        );
+        assert!(parse_layout("id:0:3").is_err());
+        assert!(parse_layout("id:1").is_err());
+        assert!(parse_layout("").is_err());
+    }
+
+    #[test]
+    fn read_fixed_width_should_trim_fields_and_skip_empty_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, "  1alice 10\r\n\r\n  2bob     \r\n   carl\r\n");
+        let layout = parse_layout("id:1:3:int,name:4:5,score:9:3:int").unwrap();
+        let (schema, batches) = read_fixed_width(&file_opts, &layout).unwrap();
+        assert_eq!(schema, layout.schema());
+        let expected = [
+            "+----+-------+-------+",
+            "| id | name  | score |",
+            "+----+-------+-------+",
+            "| 1  | alice | 10    |",
+            "| 2  | bob   |       |",
+            "|    | carl  |       |",
+            "+----+-------+-------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn read_fixed_width_should_report_the_line_of_invalid_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, "  1alice\n\n  xbob\n");
+        let layout = parse_layout("id:1:3:int,name:4:5").unwrap();
+        let err = read_fixed_width(&file_opts, &layout).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid Int32 value x of column id on line 3"
+        );
+    }
+}
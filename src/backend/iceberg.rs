@@ -0,0 +1,819 @@
+use crate::cli::connect::{IcebergLocation, SnapshotOpts};
+use anyhow::anyhow;
+use datafusion::arrow::array::{AsArray, PrimitiveArray, RecordBatch, StructArray};
+use datafusion::arrow::datatypes::{
+    DataType, Field, Fields, Int32Type, Int64Type, Schema, SchemaRef, TimeUnit,
+};
+use datafusion::common::ScalarValue;
+use datafusion::datasource::avro_to_arrow;
+use datafusion::parquet::arrow::arrow_reader::ArrowReaderMetadata;
+use datafusion::parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A snapshot of an Iceberg table of a local warehouse, the parquet data files listed by
+/// the manifests of the snapshot. Columns are matched to the files by field id
+#[derive(Debug, Clone)]
+pub struct IcebergTable {
+    schema: SchemaRef,
+    files: Vec<IcebergFile>,
+    /// The top level columns of the schema of the snapshot by field id
+    field_ids: HashMap<i64, String>,
+}
+
+/// A data file with the values of the identity partitions of the spec it was written with,
+/// by source column
+#[derive(Debug, Clone)]
+pub struct IcebergFile {
+    pub path: String,
+    pub size: u64,
+    pub record_count: u64,
+    pub partition: HashMap<String, ScalarValue>,
+}
+
+/// Partition fields of a spec whose value is the one of a column, `(partition, column)`
+type IdentityFields = Vec<(String, String)>;
+
+impl IcebergTable {
+    /// The current snapshot of the table, or the one of `--snapshot-id` or the last one
+    /// committed at or before `--timestamp`. Tables of a warehouse are given as
+    /// `namespace.table`, tables given by their location have no name
+    pub fn try_new(
+        location: &IcebergLocation,
+        table: Option<&str>,
+        opts: &SnapshotOpts,
+    ) -> anyhow::Result<Self> {
+        let location = match (location, table) {
+            (IcebergLocation::Warehouse(warehouse), Some(table)) if table.contains('.') => table
+                .split('.')
+                .fold(PathBuf::from(warehouse), |path, part| path.join(part)),
+            (IcebergLocation::Warehouse(warehouse), Some(table)) => {
+                return Err(anyhow!(
+                    "invalid iceberg table {} of the warehouse {}, expected namespace.table",
+                    table,
+                    warehouse
+                ))
+            }
+            (IcebergLocation::Warehouse(warehouse), None) => {
+                return Err(anyhow!(
+                    "a table is required for the iceberg warehouse {}, use -t namespace.table",
+                    warehouse
+                ))
+            }
+            (IcebergLocation::Table(path), None) => PathBuf::from(path),
+            (IcebergLocation::Table(path), Some(_)) => {
+                return Err(anyhow!(
+                    "-t is only supported for iceberg warehouses given as iceberg://path, {} is a table",
+                    path
+                ))
+            }
+        };
+        let metadata: Value = serde_json::from_reader(File::open(metadata_file(&location)?)?)?;
+
+        let snapshots = metadata
+            .get("snapshots")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let snapshot_id = match (opts.snapshot_id, opts.timestamp) {
+            (Some(id), _) => Some(id),
+            (None, Some(timestamp)) => {
+                let snapshot = snapshots
+                    .iter()
+                    .filter(|s| {
+                        let millis = s.get("timestamp-ms").and_then(Value::as_i64);
+                        millis.is_some_and(|ms| ms <= timestamp.timestamp_millis())
+                    })
+                    .max_by_key(|s| s.get("timestamp-ms").and_then(Value::as_i64));
+                let snapshot = snapshot.ok_or_else(|| {
+                    anyhow!(
+                        "no snapshot of {} at or before {}",
+                        location.display(),
+                        timestamp
+                    )
+                })?;
+                Some(int(snapshot, "snapshot-id")?)
+            }
+            // a new table has no snapshot yet
+            (None, None) => metadata
+                .get("current-snapshot-id")
+                .and_then(Value::as_i64)
+                .filter(|id| *id != -1),
+        };
+        let snapshot = snapshot_id
+            .map(|id| {
+                snapshots
+                    .iter()
+                    .find(|s| s.get("snapshot-id").and_then(Value::as_i64) == Some(id))
+                    .ok_or_else(|| anyhow!("snapshot {} not found in {}", id, location.display()))
+            })
+            .transpose()?;
+
+        let (schema, field_ids) = table_schema(&metadata, snapshot)?;
+        let schema = Arc::new(schema);
+        let files = match snapshot {
+            Some(snapshot) => data_files(&metadata, snapshot, &schema)?,
+            None => vec![],
+        };
+        Ok(Self {
+            schema,
+            files,
+            field_ids,
+        })
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn files(&self) -> &[IcebergFile] {
+        &self.files
+    }
+
+    /// The table column of a column of a data file, by the field id iceberg writes in the
+    /// parquet schema, so a renamed column is read from its old name and a column dropped
+    /// and added again isn't read from the data of the old one. Files written without ids,
+    /// like those of migrated tables, are matched by name
+    pub fn table_column<'a>(&'a self, field: &'a Field) -> Option<&'a str> {
+        match field.metadata().get(PARQUET_FIELD_ID_META_KEY) {
+            Some(id) => self.field_ids.get(&id.parse().ok()?).map(String::as_str),
+            None => self
+                .schema
+                .field_with_name(field.name())
+                .ok()
+                .map(|f| f.name().as_str()),
+        }
+    }
+
+    /// Whether a column of the file has the name of another table column than its own,
+    /// or of a table column while it's the data of a dropped one
+    pub fn is_renamed(&self, file_schema: &Schema) -> bool {
+        file_schema.fields().iter().any(|f| {
+            let column = self.table_column(f);
+            column != Some(f.name().as_str())
+                && (column.is_some() || self.schema.column_with_name(f.name()).is_some())
+        })
+    }
+}
+
+impl IcebergFile {
+    /// The schema of the file as read from its footer, with the field ids of the columns
+    pub fn schema(&self) -> anyhow::Result<Schema> {
+        let metadata = ArrowReaderMetadata::load(&File::open(&self.path)?, Default::default())
+            .map_err(|e| anyhow!("failed to read iceberg data file {}: {}", self.path, e))?;
+        Ok(metadata.schema().as_ref().clone())
+    }
+}
+
+/// The metadata file itself, or the latest one of a table directory as told by the
+/// `version-hint.text` of file based catalogs, the highest version otherwise
+fn metadata_file(location: &Path) -> anyhow::Result<PathBuf> {
+    if location.is_file() {
+        return Ok(location.to_path_buf());
+    }
+    let dir = location.join("metadata");
+    if let Ok(hint) = std::fs::read_to_string(dir.join("version-hint.text")) {
+        let file = dir.join(format!("v{}.metadata.json", hint.trim()));
+        if file.is_file() {
+            return Ok(file);
+        }
+    }
+    // named `v3.metadata.json` or `00003-<uuid>.metadata.json`
+    let version = |path: &Path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let digits = name
+            .trim_start_matches('v')
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>();
+        digits.parse::<u64>().ok()
+    };
+    std::fs::read_dir(&dir)
+        .map_err(|e| anyhow!("no iceberg metadata found in {}: {}", location.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.to_string_lossy().ends_with(".metadata.json"))
+        .filter_map(|p| version(&p).map(|v| (v, p)))
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| anyhow!("no iceberg metadata found in {}", location.display()))
+}
+
+/// The schema the snapshot was written with, the current one without a snapshot, and the
+/// names of its top level columns by field id
+fn table_schema(
+    metadata: &Value,
+    snapshot: Option<&Value>,
+) -> anyhow::Result<(Schema, HashMap<i64, String>)> {
+    let schema_id = snapshot
+        .and_then(|s| s.get("schema-id"))
+        .or_else(|| metadata.get("current-schema-id"))
+        .and_then(Value::as_i64);
+    let schemas = metadata.get("schemas").and_then(Value::as_array);
+    // format v1 tables may only have a single schema
+    let schema = match (schemas, schema_id) {
+        (Some(schemas), Some(id)) => schemas
+            .iter()
+            .find(|s| s.get("schema-id").and_then(Value::as_i64) == Some(id)),
+        _ => metadata.get("schema"),
+    };
+    let schema = schema.ok_or_else(|| anyhow!("no schema found in the iceberg metadata"))?;
+    let mut field_ids = HashMap::new();
+    for field in schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        field_ids.insert(int(field, "id")?, string(field, "name")?.to_string());
+    }
+    Ok((Schema::new(struct_fields(schema)?), field_ids))
+}
+
+/// The live parquet files of the snapshot, delete files aren't supported
+fn data_files(
+    metadata: &Value,
+    snapshot: &Value,
+    schema: &Schema,
+) -> anyhow::Result<Vec<IcebergFile>> {
+    let specs = partition_specs(metadata, schema)?;
+    let manifest_list = local_path(string(snapshot, "manifest-list")?)?;
+    let mut files = vec![];
+    for manifests in read_avro(&manifest_list)? {
+        let paths = manifests
+            .column_by_name("manifest_path")
+            .and_then(|c| c.as_string_opt::<i32>())
+            .ok_or_else(|| anyhow!("invalid manifest list {}", manifest_list))?;
+        let contents = manifests
+            .column_by_name("content")
+            .and_then(|c| c.as_primitive_opt::<Int32Type>());
+        let spec_ids = manifests
+            .column_by_name("partition_spec_id")
+            .and_then(|c| c.as_primitive_opt::<Int32Type>());
+        for row in 0..manifests.num_rows() {
+            if contents.is_some_and(|c| c.value(row) != 0) {
+                return Err(anyhow!("tables with row-level deletes are not supported"));
+            }
+            let spec_id = spec_ids.map(|s| s.value(row)).unwrap_or_default();
+            let identity = specs.get(&(spec_id as i64)).cloned().unwrap_or_default();
+            let manifest = local_path(paths.value(row))?;
+            for entries in read_avro(&manifest)? {
+                files.extend(manifest_files(&entries, &identity, schema, &manifest)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The files added or kept by the entries of a manifest
+fn manifest_files(
+    entries: &RecordBatch,
+    identity: &IdentityFields,
+    schema: &Schema,
+    manifest: &str,
+) -> anyhow::Result<Vec<IcebergFile>> {
+    let invalid = || anyhow!("invalid manifest {}", manifest);
+    let status = entries
+        .column_by_name("status")
+        .and_then(|c| c.as_primitive_opt::<Int32Type>())
+        .ok_or_else(invalid)?;
+    let data_file = entries
+        .column_by_name("data_file")
+        .and_then(|c| c.as_struct_opt())
+        .ok_or_else(invalid)?;
+    let paths = data_file
+        .column_by_name("file_path")
+        .and_then(|c| c.as_string_opt::<i32>())
+        .ok_or_else(invalid)?;
+    let formats = data_file
+        .column_by_name("file_format")
+        .and_then(|c| c.as_string_opt::<i32>())
+        .ok_or_else(invalid)?;
+    let counts = long_column(data_file, "record_count").ok_or_else(invalid)?;
+    let sizes = long_column(data_file, "file_size_in_bytes").ok_or_else(invalid)?;
+    let partition = data_file
+        .column_by_name("partition")
+        .and_then(|c| c.as_struct_opt());
+
+    let mut files = vec![];
+    for row in 0..entries.num_rows() {
+        // 2 is a deleted entry, 0 and 1 are existing and added files
+        if status.value(row) == 2 {
+            continue;
+        }
+        if !formats.value(row).eq_ignore_ascii_case("parquet") {
+            return Err(anyhow!(
+                "{} data files are not supported, only parquet",
+                formats.value(row)
+            ));
+        }
+        let mut values = HashMap::new();
+        for (name, column) in identity {
+            let Some(array) = partition.and_then(|p| p.column_by_name(name)) else {
+                continue;
+            };
+            let field = schema.field_with_name(column)?;
+            let value = ScalarValue::try_from_array(array, row)?.cast_to(field.data_type())?;
+            values.insert(column.clone(), value);
+        }
+        files.push(IcebergFile {
+            path: local_path(paths.value(row))?,
+            size: sizes.value(row) as u64,
+            record_count: counts.value(row) as u64,
+            partition: values,
+        });
+    }
+    Ok(files)
+}
+
+fn long_column<'a>(
+    data_file: &'a StructArray,
+    name: &str,
+) -> Option<&'a PrimitiveArray<Int64Type>> {
+    data_file
+        .column_by_name(name)
+        .and_then(|c| c.as_primitive_opt::<Int64Type>())
+}
+
+/// The identity partition fields of every spec by spec id, other transforms like `day` or
+/// `bucket[16]` don't tell the values of their column
+fn partition_specs(
+    metadata: &Value,
+    schema: &Schema,
+) -> anyhow::Result<HashMap<i64, IdentityFields>> {
+    let ids = column_ids(metadata)?;
+    let identity = |fields: &Value| -> IdentityFields {
+        fields
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|f| f.get("transform").and_then(Value::as_str) == Some("identity"))
+            .filter_map(|f| {
+                let name = f.get("name")?.as_str()?;
+                let column = ids.get(&f.get("source-id")?.as_i64()?)?;
+                schema
+                    .field_with_name(column)
+                    .ok()
+                    .map(|_| (name.to_string(), column.clone()))
+            })
+            .collect()
+    };
+
+    let mut specs = HashMap::new();
+    for spec in metadata
+        .get("partition-specs")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let fields = spec.get("fields").unwrap_or(&Value::Null);
+        specs.insert(int(spec, "spec-id")?, identity(fields));
+    }
+    // format v1 tables may only have the spec of id 0
+    if let Some(fields) = metadata.get("partition-spec") {
+        specs.entry(0).or_insert_with(|| identity(fields));
+    }
+    Ok(specs)
+}
+
+/// The names of the top level columns of every schema by field id
+fn column_ids(metadata: &Value) -> anyhow::Result<HashMap<i64, String>> {
+    let schemas = metadata.get("schemas").and_then(Value::as_array);
+    let single = metadata.get("schema").into_iter();
+    let mut ids = HashMap::new();
+    for schema in schemas.into_iter().flatten().chain(single) {
+        for field in schema
+            .get("fields")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            ids.insert(int(field, "id")?, string(field, "name")?.to_string());
+        }
+    }
+    Ok(ids)
+}
+
+fn struct_fields(schema: &Value) -> anyhow::Result<Fields> {
+    let fields = schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("invalid iceberg struct type {}", schema))?;
+    fields
+        .iter()
+        .map(|f| {
+            let required = f.get("required").and_then(Value::as_bool).unwrap_or(false);
+            let data_type = iceberg_type(f.get("type").unwrap_or(&Value::Null))?;
+            Ok(Field::new(string(f, "name")?, data_type, !required))
+        })
+        .collect()
+}
+
+/// The arrow type of an iceberg type, primitives are named and nested types are objects
+fn iceberg_type(t: &Value) -> anyhow::Result<DataType> {
+    if let Some(name) = t.as_str() {
+        let data_type = match name {
+            "boolean" => DataType::Boolean,
+            "int" => DataType::Int32,
+            "long" => DataType::Int64,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "date" => DataType::Date32,
+            "time" => DataType::Time64(TimeUnit::Microsecond),
+            "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+            "timestamptz" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            "timestamp_ns" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            "timestamptz_ns" => DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            "string" => DataType::Utf8,
+            "uuid" => DataType::FixedSizeBinary(16),
+            "binary" => DataType::Binary,
+            fixed if fixed.starts_with("fixed[") => {
+                let size = fixed["fixed[".len()..].trim_end_matches(']');
+                DataType::FixedSizeBinary(size.trim().parse()?)
+            }
+            decimal if decimal.starts_with("decimal(") => {
+                let (precision, scale) = decimal["decimal(".len()..]
+                    .trim_end_matches(')')
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("invalid iceberg type {}", decimal))?;
+                DataType::Decimal128(precision.trim().parse()?, scale.trim().parse()?)
+            }
+            v => return Err(anyhow!("iceberg type {} is not supported", v)),
+        };
+        return Ok(data_type);
+    }
+
+    match t.get("type").and_then(Value::as_str) {
+        Some("struct") => Ok(DataType::Struct(struct_fields(t)?)),
+        Some("list") => {
+            let element = iceberg_type(t.get("element").unwrap_or(&Value::Null))?;
+            let required = t.get("element-required").and_then(Value::as_bool);
+            let field = Field::new("element", element, !required.unwrap_or(false));
+            Ok(DataType::List(Arc::new(field)))
+        }
+        Some("map") => {
+            let key = iceberg_type(t.get("key").unwrap_or(&Value::Null))?;
+            let value = iceberg_type(t.get("value").unwrap_or(&Value::Null))?;
+            let required = t.get("value-required").and_then(Value::as_bool);
+            let entries = Fields::from(vec![
+                Field::new("key", key, false),
+                Field::new("value", value, !required.unwrap_or(false)),
+            ]);
+            let field = Field::new("key_value", DataType::Struct(entries), false);
+            Ok(DataType::Map(Arc::new(field), false))
+        }
+        _ => Err(anyhow!("invalid iceberg type {}", t)),
+    }
+}
+
+fn read_avro(path: &str) -> anyhow::Result<Vec<RecordBatch>> {
+    let file = BufReader::new(File::open(path)?);
+    let reader = avro_to_arrow::ReaderBuilder::new()
+        .read_schema()
+        .build(file)?;
+    Ok(reader.collect::<Result<_, _>>()?)
+}
+
+/// Files are referenced by absolute uris, only those of the local filesystem can be read
+fn local_path(uri: &str) -> anyhow::Result<String> {
+    match uri.split_once(':') {
+        Some(("file", path)) => Ok(format!("/{}", path.trim_start_matches('/'))),
+        Some((scheme, _)) if scheme.len() > 1 => Err(anyhow!(
+            "only iceberg tables of a local warehouse are supported, got {}",
+            uri
+        )),
+        _ => Ok(uri.to_string()),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> anyhow::Result<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("{} not found in iceberg metadata {}", key, value))
+}
+
+fn int(value: &Value, key: &str) -> anyhow::Result<i64> {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .ok_or_else(|| anyhow!("{} not found in iceberg metadata {}", key, value))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use apache_avro::types::{Record, Value as AvroValue};
+    use apache_avro::{Codec, Writer};
+    use chrono::DateTime;
+    use serde_json::json;
+
+    const MANIFEST_LIST: &str = r#"{
+        "type": "record",
+        "name": "manifest_file",
+        "fields": [
+            {"name": "manifest_path", "type": "string"},
+            {"name": "partition_spec_id", "type": "int"},
+            {"name": "content", "type": "int"}
+        ]
+    }"#;
+
+    const MANIFEST: &str = r#"{
+        "type": "record",
+        "name": "manifest_entry",
+        "fields": [
+            {"name": "status", "type": "int"},
+            {"name": "data_file", "type": {
+                "type": "record",
+                "name": "r2",
+                "fields": [
+                    {"name": "file_path", "type": "string"},
+                    {"name": "file_format", "type": "string"},
+                    {"name": "partition", "type": {
+                        "type": "record",
+                        "name": "r102",
+                        "fields": [{"name": "day", "type": ["null", "string"]}]
+                    }},
+                    {"name": "record_count", "type": "long"},
+                    {"name": "file_size_in_bytes", "type": "long"}
+                ]
+            }}
+        ]
+    }"#;
+
+    fn uri(path: &Path) -> String {
+        format!("file://{}", path.display())
+    }
+
+    fn write_manifest_list(path: &Path, manifests: &[&Path]) {
+        let schema = apache_avro::Schema::parse_str(MANIFEST_LIST).unwrap();
+        let mut writer = Writer::new(&schema, vec![]);
+        for manifest in manifests {
+            let mut record = Record::new(&schema).unwrap();
+            record.put("manifest_path", uri(manifest));
+            record.put("partition_spec_id", 0);
+            record.put("content", 0);
+            writer.append(record).unwrap();
+        }
+        std::fs::write(path, writer.into_inner().unwrap()).unwrap();
+    }
+
+    /// Entries of `(status, file, day, record count)`, files are 100 bytes a record
+    fn write_manifest(path: &Path, entries: &[(i32, &str, &str, i64)]) {
+        let entries = entries
+            .iter()
+            .map(|&(status, file, day, count)| (status, file, day, count, count * 100))
+            .collect::<Vec<_>>();
+        write_sized_manifest(path, &entries);
+    }
+
+    /// Entries of `(status, file, day, record count, file size)`
+    fn write_sized_manifest(path: &Path, entries: &[(i32, &str, &str, i64, i64)]) {
+        let schema = apache_avro::Schema::parse_str(MANIFEST).unwrap();
+        let mut writer = Writer::with_codec(&schema, vec![], Codec::Deflate);
+        for (status, file, day, count, size) in entries {
+            let data_file = AvroValue::Record(vec![
+                ("file_path".into(), AvroValue::String(file.to_string())),
+                ("file_format".into(), AvroValue::String("PARQUET".into())),
+                (
+                    "partition".into(),
+                    AvroValue::Record(vec![(
+                        "day".into(),
+                        AvroValue::Union(1, Box::new(AvroValue::String(day.to_string()))),
+                    )]),
+                ),
+                ("record_count".into(), AvroValue::Long(*count)),
+                ("file_size_in_bytes".into(), AvroValue::Long(*size)),
+            ]);
+            let mut record = Record::new(&schema).unwrap();
+            record.put("status", *status);
+            record.put("data_file", data_file);
+            writer.append(record).unwrap();
+        }
+        std::fs::write(path, writer.into_inner().unwrap()).unwrap();
+    }
+
+    /// The table `db.events` of a warehouse, partitioned by day. Snapshot 1 adds a file to day
+    /// a, snapshot 2 adds one to day b and deletes one of day c. Snapshots are a second apart
+    /// from 1970
+    fn warehouse(dir: &Path) {
+        let table = dir.join("db").join("events");
+        let metadata = table.join("metadata");
+        std::fs::create_dir_all(&metadata).unwrap();
+        let data = |name: &str| uri(&table.join("data").join(name));
+
+        write_manifest(
+            &metadata.join("m-1.avro"),
+            &[(1, &data("a.parquet"), "a", 2)],
+        );
+        write_manifest(
+            &metadata.join("m-2.avro"),
+            &[
+                (1, &data("b.parquet"), "b", 1),
+                (2, &data("c.parquet"), "c", 5),
+            ],
+        );
+        let (m1, m2) = (metadata.join("m-1.avro"), metadata.join("m-2.avro"));
+        write_manifest_list(&metadata.join("snap-1.avro"), &[&m1]);
+        write_manifest_list(&metadata.join("snap-2.avro"), &[&m1, &m2]);
+
+        let snapshot = |id: i64| {
+            json!({
+                "snapshot-id": id,
+                "timestamp-ms": id * 1000,
+                "schema-id": 0,
+                "manifest-list": uri(&metadata.join(format!("snap-{}.avro", id))),
+            })
+        };
+        let metadata_json = json!({
+            "format-version": 2,
+            "location": uri(&table),
+            "current-schema-id": 0,
+            "schemas": [{
+                "type": "struct",
+                "schema-id": 0,
+                "fields": [
+                    {"id": 1, "name": "id", "required": true, "type": "long"},
+                    {"id": 2, "name": "day", "required": false, "type": "string"}
+                ]
+            }],
+            "default-spec-id": 0,
+            "partition-specs": [{
+                "spec-id": 0,
+                "fields": [{"name": "day", "transform": "identity", "source-id": 2, "field-id": 1000}]
+            }],
+            "current-snapshot-id": 2,
+            "snapshots": [snapshot(1), snapshot(2)]
+        });
+        std::fs::write(metadata.join("v1.metadata.json"), "{}").unwrap();
+        std::fs::write(metadata.join("v2.metadata.json"), metadata_json.to_string()).unwrap();
+        std::fs::write(metadata.join("version-hint.text"), "2").unwrap();
+    }
+
+    /// A parquet file of the given columns of `(name, field id, value)`, one row of strings
+    /// but for the `id` column
+    fn write_data_file(path: &Path, id: i64, columns: &[(&str, i64, &str)]) -> (String, i64) {
+        use datafusion::arrow::array::{ArrayRef, Int64Array, StringArray};
+        use datafusion::parquet::arrow::ArrowWriter;
+
+        let field = |name: &str, data_type, field_id: i64| {
+            let metadata = [(PARQUET_FIELD_ID_META_KEY.to_string(), field_id.to_string())];
+            Field::new(name, data_type, name != "id").with_metadata(metadata.into())
+        };
+        let mut fields = vec![field("id", DataType::Int64, 1)];
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![id]))];
+        for (name, field_id, value) in columns {
+            fields.push(field(name, DataType::Utf8, *field_id));
+            arrays.push(Arc::new(StringArray::from(vec![*value])));
+        }
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let size = std::fs::metadata(path).unwrap().len() as i64;
+        (uri(path), size)
+    }
+
+    /// The table `db.items` partitioned by day, whose `name` column was renamed to `label`
+    /// then a new `name` column added. File a of day a was written before, file b after
+    pub(crate) fn evolved_table(dir: &Path) -> PathBuf {
+        let table = dir.join("db").join("items");
+        let metadata = table.join("metadata");
+        std::fs::create_dir_all(&metadata).unwrap();
+        let a = table.join("data").join("day=a").join("a.parquet");
+        let (a, a_size) = write_data_file(&a, 1, &[("day", 2, "a"), ("name", 3, "pen")]);
+        let b = table.join("data").join("day=b").join("b.parquet");
+        let b_columns = [("day", 2, "b"), ("label", 3, "ink"), ("name", 4, "new")];
+        let (b, b_size) = write_data_file(&b, 2, &b_columns);
+
+        let manifest = metadata.join("m-1.avro");
+        let entries = [
+            (1, a.as_str(), "a", 1, a_size),
+            (1, b.as_str(), "b", 1, b_size),
+        ];
+        write_sized_manifest(&manifest, &entries);
+        write_manifest_list(&metadata.join("snap-1.avro"), &[&manifest]);
+
+        let field = |id: i64, name: &str, kind: &str| json!({"id": id, "name": name, "required": id == 1, "type": kind});
+        let metadata_json = json!({
+            "format-version": 2,
+            "location": uri(&table),
+            "current-schema-id": 1,
+            "schemas": [
+                {
+                    "type": "struct",
+                    "schema-id": 0,
+                    "fields": [field(1, "id", "long"), field(2, "day", "string"), field(3, "name", "string")]
+                },
+                {
+                    "type": "struct",
+                    "schema-id": 1,
+                    "fields": [
+                        field(1, "id", "long"),
+                        field(2, "day", "string"),
+                        field(3, "label", "string"),
+                        field(4, "name", "string")
+                    ]
+                }
+            ],
+            "default-spec-id": 0,
+            "partition-specs": [{
+                "spec-id": 0,
+                "fields": [{"name": "day", "transform": "identity", "source-id": 2, "field-id": 1000}]
+            }],
+            "current-snapshot-id": 1,
+            "snapshots": [{
+                "snapshot-id": 1,
+                "timestamp-ms": 1000,
+                "schema-id": 1,
+                "manifest-list": uri(&metadata.join("snap-1.avro")),
+            }]
+        });
+        std::fs::write(metadata.join("v1.metadata.json"), metadata_json.to_string()).unwrap();
+        table
+    }
+
+    fn files(table: &IcebergTable) -> Vec<(String, u64, u64, Option<ScalarValue>)> {
+        table
+            .files()
+            .iter()
+            .map(|f| {
+                let name = Path::new(&f.path).file_name().unwrap();
+                let day = f.partition.get("day").cloned();
+                (
+                    name.to_string_lossy().to_string(),
+                    f.size,
+                    f.record_count,
+                    day,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn iceberg_table_should_read_the_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        warehouse(dir.path());
+        let warehouse = IcebergLocation::Warehouse(dir.path().to_string_lossy().to_string());
+        let day = |d: &str| Some(ScalarValue::Utf8(Some(d.to_string())));
+
+        let table = IcebergTable::try_new(&warehouse, Some("db.events"), &Default::default());
+        let table = table.unwrap();
+        let fields = table.schema().fields().clone();
+        assert_eq!(fields[0].data_type(), &DataType::Int64);
+        assert!(!fields[0].is_nullable() && fields[1].is_nullable());
+        assert_eq!(
+            files(&table),
+            [
+                ("a.parquet".to_string(), 200, 2, day("a")),
+                ("b.parquet".to_string(), 100, 1, day("b")),
+            ]
+        );
+        assert!(table.files()[0].path.starts_with('/'));
+
+        let opts = SnapshotOpts {
+            snapshot_id: Some(1),
+            ..Default::default()
+        };
+        let table = IcebergTable::try_new(&warehouse, Some("db.events"), &opts).unwrap();
+        assert_eq!(files(&table).len(), 1);
+        let opts = SnapshotOpts {
+            timestamp: DateTime::from_timestamp(1, 500_000_000),
+            ..Default::default()
+        };
+        let table = IcebergTable::try_new(&warehouse, Some("db.events"), &opts).unwrap();
+        assert_eq!(files(&table), [("a.parquet".to_string(), 200, 2, day("a"))]);
+        let opts = SnapshotOpts {
+            timestamp: DateTime::from_timestamp(0, 0),
+            ..Default::default()
+        };
+        assert!(IcebergTable::try_new(&warehouse, Some("db.events"), &opts).is_err());
+
+        let path = dir.path().join("db").join("events");
+        let location = IcebergLocation::Table(path.to_string_lossy().to_string());
+        let table = IcebergTable::try_new(&location, None, &Default::default()).unwrap();
+        assert_eq!(files(&table).len(), 2);
+    }
+
+    #[test]
+    fn iceberg_table_should_require_a_table_of_warehouses() {
+        let dir = tempfile::tempdir().unwrap();
+        warehouse(dir.path());
+        let warehouse = IcebergLocation::Warehouse(dir.path().to_string_lossy().to_string());
+        let path = dir.path().join("db").join("events");
+        let table = IcebergLocation::Table(path.to_string_lossy().to_string());
+        let opts = SnapshotOpts::default();
+
+        let e = IcebergTable::try_new(&warehouse, None, &opts).unwrap_err();
+        assert!(e.to_string().contains("use -t namespace.table"), "{}", e);
+        let e = IcebergTable::try_new(&warehouse, Some("events"), &opts).unwrap_err();
+        assert!(e.to_string().contains("expected namespace.table"), "{}", e);
+        let e = IcebergTable::try_new(&table, Some("db.events"), &opts).unwrap_err();
+        assert!(e.to_string().contains("is a table"), "{}", e);
+    }
+}
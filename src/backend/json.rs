@@ -0,0 +1,274 @@
+use crate::cli::connect::{is_listing, is_remote, FileOpts, JsonKey, JsonOpts};
+use anyhow::anyhow;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::json::reader::infer_json_schema_from_iterator;
+use datafusion::arrow::json::ReaderBuilder;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 8192;
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Whether the file is a single JSON document rather than newline delimited records,
+/// that is a file starting with an array, an object spanning several lines, or any file
+/// read with `--json-path`
+pub fn is_json_document(file_opts: &FileOpts, json: &JsonOpts) -> anyhow::Result<bool> {
+    // objects aren't read before connecting, they're taken as newline delimited json
+    if is_remote(&file_opts.filename) {
+        if json.json_path.is_some() {
+            return Err(anyhow!(
+                "--json-path is not supported for objects of an object store"
+            ));
+        }
+        return Ok(false);
+    }
+    let document = match &json.json_path {
+        Some(_) => true,
+        None => is_document_start(file_opts)?,
+    };
+    if document && is_listing(&file_opts.filename) {
+        return Err(anyhow!(
+            "directories and globs of json documents are not supported, only newline delimited json"
+        ));
+    }
+    Ok(document)
+}
+
+/// Read the records of a JSON document, an array of objects or a single object selected
+/// by the json path, the nested schema is inferred from all of them unless one is given
+pub fn read_json_document(
+    file_opts: &FileOpts,
+    json: &JsonOpts,
+    schema: Option<&SchemaRef>,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mut buf = vec![];
+    open(file_opts)?.read_to_end(&mut buf)?;
+    let buf = buf.strip_prefix(BOM).unwrap_or(&buf);
+    let document: Value = serde_json::from_slice(buf).map_err(|e| {
+        anyhow!(
+            "failed to parse json document {}: {}",
+            file_opts.filename,
+            e
+        )
+    })?;
+
+    let mut value = &document;
+    for key in json.json_path.iter().flat_map(|path| &path.0) {
+        value = match (key, value) {
+            (JsonKey::Key(k), Value::Object(o)) => o.get(k),
+            (JsonKey::Index(i), Value::Array(a)) => a.get(*i),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            anyhow!(
+                "json path {} not found in {}, no {} in {}",
+                json.json_path.as_ref().expect("json path is set"),
+                file_opts.filename,
+                key,
+                kind(value)
+            )
+        })?;
+    }
+    let records = match value {
+        Value::Array(records) => records.as_slice(),
+        Value::Object(_) => std::slice::from_ref(value),
+        v => {
+            return Err(anyhow!(
+                "expected an array or an object of records, got {}",
+                v
+            ))
+        }
+    };
+
+    let schema = match schema {
+        Some(schema) => schema.clone(),
+        None => Arc::new(
+            infer_json_schema_from_iterator(records.iter().map(Ok))
+                .map_err(|e| anyhow!("failed to infer the schema of the json records: {}", e))?,
+        ),
+    };
+    let mut decoder = ReaderBuilder::new(schema.clone()).build_decoder()?;
+    let mut batches = vec![];
+    for chunk in records.chunks(BATCH_SIZE) {
+        decoder.serialize(chunk)?;
+        batches.extend(decoder.flush()?);
+    }
+    Ok((schema, batches))
+}
+
+fn open(file_opts: &FileOpts) -> anyhow::Result<Box<dyn Read + Send>> {
+    let file = File::open(file_opts.sample()?)?;
+    Ok(file_opts.compression.convert_read(file)?)
+}
+
+/// Newline delimited files start with a whole object on the first line, a document either
+/// starts with an array or with an object which isn't complete on its first line
+fn is_document_start(file_opts: &FileOpts) -> anyhow::Result<bool> {
+    let mut reader = BufReader::new(open(file_opts)?);
+    let mut line = vec![];
+    while line.trim_ascii().is_empty() {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(false);
+        }
+        if let Some(rest) = line.strip_prefix(BOM) {
+            line = rest.to_vec();
+        }
+    }
+    let line = line.trim_ascii();
+    Ok(line.starts_with(b"[") || serde_json::from_slice::<Value>(line).is_err_and(|e| e.is_eof()))
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        _ => "value",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::connect::JsonPath;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+    use std::io::Write;
+
+    fn file(dir: &std::path::Path, name: &str, content: &[u8]) -> FileOpts {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        let path = path.to_string_lossy().to_string();
+        FileOpts::new(path, ".json".to_string(), FileCompressionType::UNCOMPRESSED)
+    }
+
+    fn json_path(keys: &[&str]) -> JsonOpts {
+        let keys = keys.iter().map(|k| match k.parse() {
+            Ok(i) => JsonKey::Index(i),
+            Err(_) => JsonKey::Key(k.to_string()),
+        });
+        JsonOpts {
+            json_path: Some(JsonPath(keys.collect())),
+        }
+    }
+
+    fn read(file_opts: &FileOpts, json: &JsonOpts) -> anyhow::Result<String> {
+        let (_, batches) = read_json_document(file_opts, json, None)?;
+        Ok(pretty_format_batches(&batches)?.to_string())
+    }
+
+    #[test]
+    fn is_document_start_should_tell_documents_from_newline_delimited_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases: [(&str, &[u8], bool); 6] = [
+            ("records", b"{\"id\": 1}\n{\"id\": 2}\n", false),
+            ("array", b"\n  [{\"id\": 1},\n {\"id\": 2}]", true),
+            ("object", b"{\n  \"data\": [{\"id\": 1}]\n}", true),
+            ("bom", b"\xEF\xBB\xBF[{\"id\": 1}]", true),
+            ("bom-records", b"\xEF\xBB\xBF{\"id\": 1}\n", false),
+            ("empty", b"\n\n", false),
+        ];
+        for (name, content, document) in cases {
+            let file_opts = file(dir.path(), name, content);
+            assert_eq!(is_document_start(&file_opts).unwrap(), document, "{}", name);
+        }
+    }
+
+    #[test]
+    fn is_json_document_should_be_true_with_a_json_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(dir.path(), "records.json", b"{\"id\": 1}\n");
+        assert!(!is_json_document(&file_opts, &JsonOpts::default()).unwrap());
+        assert!(is_json_document(&file_opts, &json_path(&["id"])).unwrap());
+    }
+
+    #[test]
+    fn read_json_document_should_read_a_top_level_array() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = b"[{\"id\": 1, \"tags\": [\"a\"]}, {\"id\": 2, \"name\": \"Ada\"}]";
+        let file_opts = file(dir.path(), "users.json", content);
+        let expected = [
+            "+----+------+------+",
+            "| id | tags | name |",
+            "+----+------+------+",
+            "| 1  | [a]  |      |",
+            "| 2  |      | Ada  |",
+            "+----+------+------+",
+        ];
+        assert_eq!(
+            read(&file_opts, &JsonOpts::default()).unwrap(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn read_json_document_should_read_the_object_selected_by_the_json_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = b"{\"results\": [{\"meta\": {\"id\": 7, \"ok\": true}}]}";
+        let file_opts = file(dir.path(), "response.json", content);
+        let expected = [
+            "+----+------+",
+            "| id | ok   |",
+            "+----+------+",
+            "| 7  | true |",
+            "+----+------+",
+        ];
+        let json = json_path(&["results", "0", "meta"]);
+        assert_eq!(read(&file_opts, &json).unwrap(), expected.join("\n"));
+    }
+
+    #[test]
+    fn read_json_document_should_report_a_json_path_matching_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(dir.path(), "response.json", b"{\"results\": [{\"id\": 1}]}");
+
+        let e = read(&file_opts, &json_path(&["results", "3"])).unwrap_err();
+        let expected = format!(
+            "json path $.results[3] not found in {}, no [3] in array",
+            file_opts.filename
+        );
+        assert_eq!(e.to_string(), expected);
+        let e = read(&file_opts, &json_path(&["data", "items"])).unwrap_err();
+        assert!(e.to_string().ends_with(", no data in object"), "{}", e);
+        let e = read(&file_opts, &json_path(&["results", "0", "id"])).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "expected an array or an object of records, got 1"
+        );
+    }
+
+    #[test]
+    fn read_json_document_should_skip_a_bom() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(dir.path(), "bom.json", b"\xEF\xBB\xBF[{\"id\": 1}]");
+        let expected = ["+----+", "| id |", "+----+", "| 1  |", "+----+"];
+        assert_eq!(
+            read(&file_opts, &JsonOpts::default()).unwrap(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn read_json_document_should_read_a_compressed_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder
+            .write_all(b"{\n  \"data\": [{\"id\": 1}, {\"id\": 2}]\n}")
+            .unwrap();
+        let path = dir.path().join("data.json.gz");
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        let file_opts = FileOpts::new(
+            path.to_string_lossy().to_string(),
+            ".json.gz".to_string(),
+            FileCompressionType::GZIP,
+        );
+
+        assert!(is_json_document(&file_opts, &JsonOpts::default()).unwrap());
+        let expected = ["+----+", "| id |", "+----+", "| 1  |", "| 2  |", "+----+"];
+        let json = json_path(&["data"]);
+        assert_eq!(read(&file_opts, &json).unwrap(), expected.join("\n"));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::cli::connect::FileOpts;
+use anyhow::anyhow;
+use datafusion::arrow::array::{Array, ArrayRef, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 8192;
+
+const COMMON: &str = r#"^(?P<host>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<time>[^\]]+)\] "(?:(?P<method>[A-Z]+) (?P<path>\S+)(?: (?P<protocol>[^"]*))?|[^"]*)" (?P<status>\d{3}) (?P<bytes>\S+)"#;
+const COMBINED: &str = r#" "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)""#;
+const SYSLOG: &str = r"^(?:<(?P<priority>\d+)>)?(?P<time>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<program>[^:\[\s]+)(?:\[(?P<pid>\d+)\])?: (?P<message>.*)$";
+
+/// The regex of a preset, `common` or `combined` (`apache` and `nginx`) access logs or
+/// `syslog`, or a pattern whose named groups are the columns
+pub fn log_regex(spec: &str) -> anyhow::Result<Regex> {
+    let pattern = match spec.to_lowercase().as_str() {
+        "common" => COMMON.to_string(),
+        "combined" | "apache" | "nginx" => format!("{}{}", COMMON, COMBINED),
+        "syslog" => SYSLOG.to_string(),
+        _ => spec.to_string(),
+    };
+    let regex = Regex::new(&pattern)?;
+    if regex.capture_names().flatten().next().is_none() {
+        return Err(anyhow!(
+            "invalid regex {}, columns are named groups like (?P<status>\\d+)",
+            spec
+        ));
+    }
+    Ok(regex)
+}
+
+/// Read the lines of a log file matching the regex, each named group is a string column
+/// unless `schema` gives it another type. Groups that didn't match and `-` values of typed
+/// columns are null, lines that don't match are skipped
+pub fn read_log(
+    file_opts: &FileOpts,
+    regex: &Regex,
+    schema: Option<&SchemaRef>,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let fields = regex
+        .capture_names()
+        .flatten()
+        .map(|name| {
+            let data_type = schema
+                .and_then(|s| s.field_with_name(name).ok())
+                .map_or(DataType::Utf8, |f| f.data_type().clone());
+            Field::new(name, data_type, true)
+        })
+        .collect::<Vec<_>>();
+    let log_schema = Arc::new(Schema::new(fields));
+
+    let file = File::open(&file_opts.filename)?;
+    let reader = BufReader::new(file_opts.compression.convert_read(file)?);
+    let mut batches = vec![];
+    let mut rows = vec![];
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        let Some(captures) = regex.captures(line) else {
+            continue;
+        };
+        let values = log_schema
+            .fields()
+            .iter()
+            .map(|f| captures.name(f.name()).map(|m| m.as_str().to_string()))
+            .collect::<Vec<_>>();
+        rows.push((i + 1, values));
+        if rows.len() == BATCH_SIZE {
+            batches.push(to_batch(&log_schema, &rows)?);
+            rows.clear();
+        }
+    }
+    if !rows.is_empty() {
+        batches.push(to_batch(&log_schema, &rows)?);
+    }
+    if batches.is_empty() {
+        return Err(anyhow!(
+            "no lines of {} match the regex",
+            file_opts.filename
+        ));
+    }
+    Ok((log_schema, batches))
+}
+
+fn to_batch(
+    schema: &SchemaRef,
+    rows: &[(usize, Vec<Option<String>>)],
+) -> anyhow::Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let is_string = field.data_type() == &DataType::Utf8;
+            let values = rows
+                .iter()
+                .map(|(_, values)| values[i].as_deref())
+                .map(|v| v.filter(|v| is_string || *v != "-"))
+                .collect::<StringArray>();
+            let array = cast(&values, field.data_type())?;
+            // values that can't be cast are made null, the first one is reported instead
+            let invalid = (0..values.len()).find(|r| values.is_valid(*r) && array.is_null(*r));
+            if let Some(row) = invalid {
+                return Err(anyhow!(
+                    "invalid {} value {} of column {} on line {}",
+                    field.data_type(),
+                    values.value(row),
+                    field.name(),
+                    rows[row].0
+                ));
+            }
+            Ok(array)
+        })
+        .collect::<anyhow::Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+    fn file(dir: &tempfile::TempDir, content: &str) -> FileOpts {
+        let path = dir.path().join("access.log");
+        std::fs::write(&path, content).unwrap();
+        FileOpts::new(
+            path.to_string_lossy().to_string(),
+            "log".to_string(),
+            FileCompressionType::UNCOMPRESSED,
+        )
+    }
+
+    fn schema(fields: &[(&str, DataType)]) -> SchemaRef {
+        let fields = fields
+            .iter()
+            .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
+            .collect::<Vec<_>>();
+        Arc::new(Schema::new(fields))
+    }
+
+    #[test]
+    fn log_regex_should_require_named_groups() {
+        let regex = log_regex("nginx").unwrap();
+        assert!(regex.capture_names().flatten().any(|n| n == "user_agent"));
+        assert!(log_regex(r"^(\d+)").is_err());
+        assert!(log_regex(r"^(?P<id>\d+").is_err());
+    }
+
+    #[test]
+    fn read_log_should_read_matching_lines_with_typed_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(
+            &dir,
+            "10.0.0.1 - - [01/May/2024:10:30:00 +0000] \"GET /index.html HTTP/1.1\" 200 512\r\n\
+             not an access log line\r\n\
+             10.0.0.2 - bob [01/May/2024:10:31:00 +0000] \"-\" 400 -\r\n",
+        );
+        let regex = log_regex("common").unwrap();
+        let typed = schema(&[("status", DataType::Int16), ("bytes", DataType::Int64)]);
+        let (log_schema, batches) = read_log(&file_opts, &regex, Some(&typed)).unwrap();
+        assert_eq!(
+            log_schema.field_with_name("status").unwrap().data_type(),
+            &DataType::Int16
+        );
+        let batch = batches[0].project(&[0, 2, 4, 5, 7, 8]).unwrap();
+        let expected = [
+            "+----------+------+--------+-------------+--------+-------+",
+            "| host     | user | method | path        | status | bytes |",
+            "+----------+------+--------+-------------+--------+-------+",
+            "| 10.0.0.1 | -    | GET    | /index.html | 200    | 512   |",
+            "| 10.0.0.2 | bob  |        |             | 400    |       |",
+            "+----------+------+--------+-------------+--------+-------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&[batch]).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn read_log_should_report_the_line_of_invalid_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, "skipped\nid=1\n\nid=x\n");
+        let regex = log_regex(r"^id=(?P<id>\S+)$").unwrap();
+        let typed = schema(&[("id", DataType::Int32)]);
+        let err = read_log(&file_opts, &regex, Some(&typed)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid Int32 value x of column id on line 4"
+        );
+        let err = read_log(&file(&dir, "skipped\n"), &regex, None).unwrap_err();
+        assert!(err.to_string().starts_with("no lines of "));
+    }
+}
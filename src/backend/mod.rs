@@ -1 +1,384 @@
+pub(crate) mod archive;
+pub(crate) mod delta;
 pub mod df;
+pub(crate) mod excel;
+pub(crate) mod fixed_width;
+pub(crate) mod iceberg;
+pub(crate) mod json;
+pub(crate) mod logs;
+pub(crate) mod orc;
+pub mod pl;
+pub(crate) mod schema;
+pub(crate) mod sniff;
+pub(crate) mod sqlite;
+pub(crate) mod stdin;
+pub(crate) mod store;
+pub(crate) mod xml;
+
+use crate::backend::df::DataFusionBackend;
+use crate::backend::pl::PolarsBackend;
+use crate::cli::{ConnectOpts, DescribeOpts, ExportOpts, HeadOpts, SchemaOpts, SqlOpts};
+use crate::{Backend, ReplDisplay};
+use clap::ValueEnum;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    #[default]
+    #[value(name = "datafusion")]
+    DataFusion,
+    #[value(name = "polars")]
+    Polars,
+}
+
+/// The temporary directory of backends used on their own, where standard input and archive
+/// members are written to be connected. Every `ReplBackend` has one of its own
+pub(crate) fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("data-forge-{}", std::process::id()))
+}
+
+enum Engine {
+    DataFusion(DataFusionBackend),
+    Polars(Box<PolarsBackend>),
+}
+
+/// The backend used by the repl, it delegates to the selected engine and remembers the
+/// connected datasets so they can be registered again when the engine is switched.
+pub struct ReplBackend {
+    engine: Engine,
+    connected: Vec<ConnectOpts>,
+    /// The scratch directory of the session, removed by the `ReplContext` owning it
+    scratch_dir: PathBuf,
+}
+
+pub(crate) enum ReplOutput<A, B> {
+    DataFusion(A),
+    Polars(B),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $method:ident($($arg:expr),*)) => {
+        match &$self.engine {
+            Engine::DataFusion(b) => ReplOutput::DataFusion(b.$method($($arg),*).await?),
+            Engine::Polars(b) => ReplOutput::Polars(b.$method($($arg),*).await?),
+        }
+    };
+}
+
+impl Engine {
+    fn new(kind: BackendKind) -> Self {
+        match kind {
+            BackendKind::DataFusion => Engine::DataFusion(DataFusionBackend::new()),
+            BackendKind::Polars => Engine::Polars(Box::default()),
+        }
+    }
+
+    fn kind(&self) -> BackendKind {
+        match self {
+            Engine::DataFusion(_) => BackendKind::DataFusion,
+            Engine::Polars(_) => BackendKind::Polars,
+        }
+    }
+
+    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
+        match self {
+            Engine::DataFusion(b) => b.connect(opts).await,
+            Engine::Polars(b) => b.connect(opts).await,
+        }
+    }
+}
+
+impl ReplBackend {
+    pub fn new(kind: BackendKind) -> Self {
+        static SESSIONS: AtomicUsize = AtomicUsize::new(0);
+        let session = SESSIONS.fetch_add(1, Ordering::Relaxed);
+        let mut scratch_dir = scratch_dir().into_os_string();
+        scratch_dir.push(format!("-{}", session));
+        Self {
+            engine: Engine::new(kind),
+            connected: vec![],
+            scratch_dir: scratch_dir.into(),
+        }
+    }
+
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch_dir
+    }
+
+    pub fn kind(&self) -> BackendKind {
+        self.engine.kind()
+    }
+}
+
+impl Default for ReplBackend {
+    fn default() -> Self {
+        Self::new(BackendKind::default())
+    }
+}
+
+impl Backend for ReplBackend {
+    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
+        let mut opts = opts.clone();
+        opts.scratch_dir
+            .get_or_insert_with(|| self.scratch_dir.clone());
+        self.engine.connect(&opts).await?;
+        self.connected.retain(|c| c.name != opts.name);
+        self.connected.push(opts);
+        Ok(())
+    }
+
+    fn scratch_dir(&self) -> PathBuf {
+        self.scratch_dir.clone()
+    }
+
+    async fn list(&self) -> anyhow::Result<impl ReplDisplay> {
+        Ok(dispatch!(self, list()))
+    }
+
+    async fn schema(&self, opts: SchemaOpts) -> anyhow::Result<impl ReplDisplay> {
+        Ok(dispatch!(self, schema(opts)))
+    }
+
+    async fn describe(&self, opts: DescribeOpts) -> anyhow::Result<impl ReplDisplay> {
+        Ok(dispatch!(self, describe(opts)))
+    }
+
+    async fn head(&self, opts: HeadOpts) -> anyhow::Result<impl ReplDisplay> {
+        Ok(dispatch!(self, head(opts)))
+    }
+
+    async fn sql(&self, opts: SqlOpts) -> anyhow::Result<impl ReplDisplay> {
+        Ok(dispatch!(self, sql(opts)))
+    }
+
+    async fn export(&self, opts: ExportOpts) -> anyhow::Result<usize> {
+        match &self.engine {
+            Engine::DataFusion(b) => b.export(opts).await,
+            Engine::Polars(b) => b.export(opts).await,
+        }
+    }
+
+    async fn switch(&mut self, kind: BackendKind) -> anyhow::Result<()> {
+        if self.kind() == kind {
+            return Ok(());
+        }
+        // the current engine is kept if any dataset can't be registered to the new one
+        let mut engine = Engine::new(kind);
+        for opts in &self.connected {
+            engine.connect(opts).await.map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to register dataset {} to {}: {}",
+                    opts.name,
+                    kind,
+                    e
+                )
+            })?;
+        }
+        self.engine = engine;
+        Ok(())
+    }
+}
+
+impl<A: ReplDisplay, B: ReplDisplay> ReplDisplay for ReplOutput<A, B> {
+    async fn display(self) -> anyhow::Result<String> {
+        match self {
+            ReplOutput::DataFusion(a) => a.display().await,
+            ReplOutput::Polars(b) => b.display().await,
+        }
+    }
+}
+
+impl Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackendKind::DataFusion => write!(f, "datafusion"),
+            BackendKind::Polars => write!(f, "polars"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::connect::{conn_parser, DataSetConn, IcebergLocation};
+    use anyhow::anyhow;
+
+    async fn query(backend: &ReplBackend, sql: &str) -> anyhow::Result<String> {
+        let opts = SqlOpts::new(sql.to_string());
+        backend.sql(opts).await?.display().await
+    }
+
+    fn connect_opts(path: &std::path::Path, name: &str) -> anyhow::Result<ConnectOpts> {
+        let conn = conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        Ok(ConnectOpts::new(conn, None, name.to_string()))
+    }
+
+    #[tokio::test]
+    async fn switch_should_register_the_connected_datasets_again() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sales.csv");
+        std::fs::write(&path, "id,total\n1,10\n2,20\n")?;
+        let mut backend = ReplBackend::new(BackendKind::DataFusion);
+        backend.connect(&connect_opts(&path, "sales")?).await?;
+        let sql = "SELECT sum(total) AS total FROM sales";
+        let expected = [
+            "+-------+",
+            "| total |",
+            "+-------+",
+            "| 30    |",
+            "+-------+",
+        ];
+        assert_eq!(query(&backend, sql).await?, expected.join("\n"));
+
+        backend.switch(BackendKind::Polars).await?;
+        assert_eq!(backend.kind(), BackendKind::Polars);
+        assert_eq!(query(&backend, sql).await?, expected.join("\n"));
+
+        backend.switch(BackendKind::DataFusion).await?;
+        assert_eq!(backend.kind(), BackendKind::DataFusion);
+        assert_eq!(query(&backend, sql).await?, expected.join("\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn switch_should_keep_the_engine_if_a_dataset_fails_to_register() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("shop.db");
+        let db = rusqlite::Connection::open(&path)?;
+        db.execute_batch("CREATE TABLE items (id INTEGER); INSERT INTO items VALUES (1);")?;
+        drop(db);
+        // the polars backend registers a single sqlite table given with -t
+        let mut backend = ReplBackend::new(BackendKind::DataFusion);
+        let conn = DataSetConn::Sqlite(path.to_string_lossy().to_string());
+        let opts = ConnectOpts::new(conn, None, "shop".to_string());
+        backend.connect(&opts).await?;
+
+        let err = backend.switch(BackendKind::Polars).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("failed to register dataset shop to polars: "));
+        assert_eq!(backend.kind(), BackendKind::DataFusion);
+        let expected = ["+----+", "| id |", "+----+", "| 1  |", "+----+"];
+        assert_eq!(
+            query(&backend, "SELECT id FROM shop.items").await?,
+            expected.join("\n")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn text_files_should_be_fixed_width_only_with_a_layout() -> anyhow::Result<()> {
+        use crate::CmdExecutor;
+        use clap::Parser;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("cust.txt");
+        std::fs::write(&path, "id,name\n1,ann\n")?;
+        let extract = dir.path().join("CUST.DAT");
+        std::fs::write(&extract, "00000001ann  \n00000002bob  \n")?;
+        let connect = |path: &Path, name: &str, layout: Option<&str>| {
+            let path = path.to_string_lossy().to_string();
+            let mut args = vec!["connect", path.as_str(), "-n", name];
+            args.extend(layout.map(|l| ["--layout", l]).into_iter().flatten());
+            ConnectOpts::try_parse_from(args).map(Box::new)
+        };
+
+        let mut backend = ReplBackend::new(BackendKind::DataFusion);
+        connect(&path, "cust", None)?.execute(&mut backend).await?;
+        let layout = Some("id:1:8:int,name:9:5");
+        connect(&extract, "extract", layout)?
+            .execute(&mut backend)
+            .await?;
+        let sql = "SELECT cust.name, extract.id FROM cust JOIN extract ON cust.name = extract.name";
+        let expected = [
+            "+------+----+",
+            "| name | id |",
+            "+------+----+",
+            "| ann  | 1  |",
+            "+------+----+",
+        ];
+        assert_eq!(query(&backend, sql).await?, expected.join("\n"));
+
+        let csv = dir.path().join("cust.csv");
+        std::fs::write(&csv, "id,name\n1,ann\n")?;
+        let err = connect(&csv, "csv", layout)?
+            .execute(&mut backend)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("--layout is only supported"),
+            "{err}"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orc_files_should_be_read_by_both_engines() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("scalars.orc");
+        crate::backend::orc::tests::write_scalars(&path);
+        let mut backend = ReplBackend::new(BackendKind::DataFusion);
+        backend.connect(&connect_opts(&path, "scalars")?).await?;
+
+        let sql = "SELECT name, sum(id) AS ids FROM scalars WHERE flag GROUP BY name";
+        let expected = [
+            "+------+-----+",
+            "| name | ids |",
+            "+------+-----+",
+            "| a    | 12  |",
+            "+------+-----+",
+        ];
+        for kind in [BackendKind::DataFusion, BackendKind::Polars] {
+            backend.switch(kind).await?;
+            assert_eq!(
+                query(&backend, sql).await?,
+                expected.join("\n"),
+                "{:?}",
+                kind
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn iceberg_tables_should_read_renamed_and_added_columns_by_field_id() -> anyhow::Result<()>
+    {
+        let dir = tempfile::tempdir()?;
+        let table = crate::backend::iceberg::tests::evolved_table(dir.path());
+        let location = IcebergLocation::Table(table.to_string_lossy().to_string());
+        let opts = ConnectOpts::new(DataSetConn::Iceberg(location), None, "items".to_string());
+        let mut backend = ReplBackend::new(BackendKind::DataFusion);
+        backend.connect(&opts).await?;
+
+        let sql = "SELECT id, day, label, name FROM items ORDER BY id";
+        let expected = [
+            "+----+-----+-------+------+",
+            "| id | day | label | name |",
+            "+----+-----+-------+------+",
+            "| 1  | a   | pen   |      |",
+            "| 2  | b   | ink   | new  |",
+            "+----+-----+-------+------+",
+        ];
+        // the name of file a is the old label, its row groups can't be pruned by it
+        let filtered = "SELECT id FROM items WHERE name IS NULL AND label = 'pen'";
+        let one = ["+----+", "| id |", "+----+", "| 1  |", "+----+"];
+        for kind in [BackendKind::DataFusion, BackendKind::Polars] {
+            backend.switch(kind).await?;
+            assert_eq!(
+                query(&backend, sql).await?,
+                expected.join("\n"),
+                "{:?}",
+                kind
+            );
+            assert_eq!(
+                query(&backend, filtered).await?,
+                one.join("\n"),
+                "{:?}",
+                kind
+            );
+        }
+        Ok(())
+    }
+}
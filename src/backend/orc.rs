@@ -0,0 +1,209 @@
+//! ORC files read with the orc-rust crate, one stripe at a time.
+//!
+//! Every ORC type is decoded by orc-rust into its arrow counterpart, union columns into
+//! sparse arrow unions, and only the projected columns of a stripe are read.
+
+use anyhow::{anyhow, bail};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use orc_rust::projection::ProjectionMask;
+use orc_rust::ArrowReaderBuilder;
+use std::fs::File;
+use std::io::Read;
+
+const MAGIC: &[u8; 3] = b"ORC";
+
+/// The footer of an ORC file, stripes are read on demand
+#[derive(Debug)]
+pub struct OrcFile {
+    path: String,
+    /// The offset of each stripe, which tells it apart when reading
+    stripes: Vec<u64>,
+    schema: SchemaRef,
+}
+
+impl OrcFile {
+    pub fn try_new(path: &str) -> anyhow::Result<Self> {
+        let builder = Self::builder(path)?;
+        let stripes = builder
+            .file_metadata()
+            .stripe_metadatas()
+            .iter()
+            .map(|stripe| stripe.offset())
+            .collect();
+        Ok(Self {
+            path: path.to_string(),
+            stripes,
+            schema: builder.schema(),
+        })
+    }
+
+    fn builder(path: &str) -> anyhow::Result<ArrowReaderBuilder<File>> {
+        let mut file = File::open(path)?;
+        let mut magic = [0; 3];
+        if file.read_exact(&mut magic).is_err() || &magic != MAGIC {
+            bail!("not an orc file: {}", path);
+        }
+        ArrowReaderBuilder::try_new(file).map_err(|e| anyhow!("invalid orc file {}: {}", path, e))
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn num_stripes(&self) -> usize {
+        self.stripes.len()
+    }
+
+    /// Read a stripe with the given columns of the schema, every column if omitted
+    pub fn read_stripe(
+        &self,
+        stripe: usize,
+        projection: Option<&[usize]>,
+    ) -> anyhow::Result<impl Iterator<Item = anyhow::Result<RecordBatch>> + Send> {
+        let builder = Self::builder(&self.path)?;
+        // orc-rust reads the projected columns in file order, they're put back in the
+        // order asked for once read
+        let (mask, order) = match projection {
+            Some(projection) => {
+                let mut columns = projection.to_vec();
+                columns.sort_unstable();
+                columns.dedup();
+                let names = columns
+                    .iter()
+                    .map(|i| self.schema.field(*i).name())
+                    .collect::<Vec<_>>();
+                let root = builder.file_metadata().root_data_type();
+                let order: Vec<_> = projection
+                    .iter()
+                    .map(|i| columns.binary_search(i).unwrap_or_default())
+                    .collect();
+                (ProjectionMask::named_roots(root, &names), Some(order))
+            }
+            None => (ProjectionMask::all(), None),
+        };
+        let offset = self.stripes[stripe] as usize;
+        let reader = builder
+            .with_projection(mask)
+            .with_file_byte_range(offset..offset + 1)
+            .build();
+        let path = self.path.clone();
+        Ok(reader.map(move |batch| {
+            let batch = batch.map_err(|e| anyhow!("invalid orc file {}: {}", path, e))?;
+            match &order {
+                Some(order) => Ok(batch.project(order)?),
+                None => Ok(batch),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use datafusion::arrow::array::{
+        ArrayRef, BinaryArray, BooleanArray, Float64Array, Int16Array, Int64Array, StringArray,
+    };
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use orc_rust::ArrowWriterBuilder;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// Scalar columns with nulls, each batch written as a stripe of its own
+    pub(crate) fn write_scalars(path: &Path) {
+        let batch = |offset: i64| {
+            let columns: Vec<(&str, ArrayRef)> = vec![
+                (
+                    "id",
+                    Arc::new(Int64Array::from(vec![offset + 1, offset + 2])),
+                ),
+                ("name", Arc::new(StringArray::from(vec![Some("a"), None]))),
+                ("flag", Arc::new(BooleanArray::from(vec![true, false]))),
+                ("score", Arc::new(Float64Array::from(vec![1.5, -2.0]))),
+                ("qty", Arc::new(Int16Array::from(vec![None, Some(-3)]))),
+                ("raw", Arc::new(BinaryArray::from(vec![&b"\x01"[..], b""]))),
+            ];
+            RecordBatch::try_from_iter(columns).unwrap()
+        };
+        let file = File::create(path).unwrap();
+        let mut writer = ArrowWriterBuilder::new(file, batch(0).schema())
+            .try_build()
+            .unwrap();
+        for offset in [0, 10] {
+            writer.write(&batch(offset)).unwrap();
+            writer.flush_stripe().unwrap();
+        }
+        writer.close().unwrap();
+    }
+
+    fn read(path: &Path, projection: Option<&[usize]>) -> anyhow::Result<String> {
+        let orc = OrcFile::try_new(&path.to_string_lossy())?;
+        let mut batches = vec![];
+        for stripe in 0..orc.num_stripes() {
+            for batch in orc.read_stripe(stripe, projection)? {
+                batches.push(batch?);
+            }
+        }
+        Ok(pretty_format_batches(&batches)?.to_string())
+    }
+
+    #[test]
+    fn orc_file_should_read_each_stripe_with_the_projected_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scalars.orc");
+        write_scalars(&path);
+        let orc = OrcFile::try_new(&path.to_string_lossy()).unwrap();
+        assert_eq!(orc.num_stripes(), 2);
+        let expected = [
+            "+----+------+-------+-------+-----+-----+",
+            "| id | name | flag  | score | qty | raw |",
+            "+----+------+-------+-------+-----+-----+",
+            "| 1  | a    | true  | 1.5   |     | 01  |",
+            "| 2  |      | false | -2.0  | -3  |     |",
+            "| 11 | a    | true  | 1.5   |     | 01  |",
+            "| 12 |      | false | -2.0  | -3  |     |",
+            "+----+------+-------+-------+-----+-----+",
+        ];
+        assert_eq!(read(&path, None).unwrap(), expected.join("\n"));
+
+        let batches = orc
+            .read_stripe(1, Some(&[4, 0]))
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        let expected = [
+            "+-----+----+",
+            "| qty | id |",
+            "+-----+----+",
+            "|     | 11 |",
+            "| -3  | 12 |",
+            "+-----+----+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn orc_file_should_reject_truncated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scalars.orc");
+        write_scalars(&path);
+        let file = std::fs::read(&path).unwrap();
+        let truncated = dir.path().join("truncated.orc");
+        for len in 0..file.len() {
+            std::fs::write(&truncated, &file[..len]).unwrap();
+            assert!(
+                read(&truncated, None).is_err(),
+                "truncated to {} bytes",
+                len
+            );
+        }
+
+        let text = dir.path().join("researchers");
+        std::fs::write(&text, "ORCID,name\n0000-0002-1825-0097,Josiah Carberry\n").unwrap();
+        let e = read(&text, None).unwrap_err();
+        assert!(e.to_string().starts_with("invalid orc file"), "{}", e);
+    }
+}
@@ -0,0 +1,686 @@
+use crate::backend::archive::archive_members;
+use crate::backend::delta::DeltaTable;
+use crate::backend::df::{partition_names, write_dataframe};
+use crate::backend::excel::read_excel;
+use crate::backend::fixed_width::read_fixed_width;
+use crate::backend::iceberg::IcebergTable;
+use crate::backend::json::{is_json_document, read_json_document};
+use crate::backend::logs::read_log;
+use crate::backend::orc::OrcFile;
+use crate::backend::sqlite::SqliteTable;
+use crate::backend::xml::read_xml;
+use crate::cli::connect::{is_listing, is_remote, CsvOpts, DataSetConn, FileOpts};
+use crate::cli::{ConnectOpts, DescribeOpts, ExportOpts, HeadOpts, SchemaOpts, SqlOpts};
+use crate::{Backend, ReplDisplay};
+use anyhow::anyhow;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes;
+use datafusion::arrow::ipc::reader::FileReader;
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::arrow::json::reader::infer_json_schema;
+use datafusion::arrow::json::ReaderBuilder;
+use datafusion::arrow::util::pretty::pretty_format_batches;
+use datafusion::datasource::avro_to_arrow;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::SessionContext;
+use polars::io::HiveOptions;
+use polars::prelude::*;
+use polars::sql::SQLContext;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::ops::Deref;
+
+pub struct PolarsBackend(SQLContext);
+
+impl PolarsBackend {
+    pub fn new() -> Self {
+        Self(SQLContext::new())
+    }
+
+    fn table(&self, name: &str) -> anyhow::Result<LazyFrame> {
+        let mut ctx = self.0.clone();
+        let lf = ctx.execute(&format!("SELECT * FROM {}", name))?;
+        Ok(lf)
+    }
+}
+
+impl Default for PolarsBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for PolarsBackend {
+    type Target = SQLContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Backend for PolarsBackend {
+    async fn connect(&mut self, opts: &ConnectOpts) -> anyhow::Result<()> {
+        if let DataSetConn::Parquet(file_opts)
+        | DataSetConn::Arrow(file_opts)
+        | DataSetConn::Csv(file_opts)
+        | DataSetConn::Json(file_opts) = opts.conn()
+        {
+            if is_remote(&file_opts.filename) {
+                return Err(anyhow!(
+                    "s3 and http urls are not supported by the polars backend"
+                ));
+            }
+        }
+        let lf = match opts.conn() {
+            DataSetConn::Postgres(_) => {
+                return Err(anyhow!("postgres is not supported by the polars backend"))
+            }
+            DataSetConn::Sqlite(path) => {
+                let table = opts.table.as_deref().ok_or_else(|| {
+                    anyhow!("polars backend can only register a single sqlite table, use -t")
+                })?;
+                read_sqlite(path, table)?
+            }
+            DataSetConn::Parquet(file_opts) => {
+                let args = ScanArgsParquet {
+                    hive_options: hive_options(file_opts).await?,
+                    ..Default::default()
+                };
+                LazyFrame::scan_parquet(&file_opts.filename, args)?
+            }
+            DataSetConn::Arrow(file_opts) => {
+                let args = ScanArgsIpc {
+                    hive_options: hive_options(file_opts).await?,
+                    ..Default::default()
+                };
+                LazyFrame::scan_ipc(&file_opts.filename, args)?
+            }
+            DataSetConn::Avro(filename) => read_avro(filename)?,
+            DataSetConn::Orc(filename) => read_orc(filename)?,
+            DataSetConn::Archive(archive) => {
+                let members = archive_members(opts, archive)?;
+                let [member] = members.as_slice() else {
+                    return Err(anyhow!(
+                        "polars backend can only register a single archive member, use --member"
+                    ));
+                };
+                let member = ConnectOpts {
+                    name: opts.name.clone(),
+                    ..member.clone()
+                };
+                return Box::pin(self.connect(&member)).await;
+            }
+            DataSetConn::Stdin => return Err(anyhow!("standard input is read before connecting")),
+            DataSetConn::Delta(location) => {
+                read_delta(&DeltaTable::try_new(location, &opts.snapshot)?)?
+            }
+            DataSetConn::Iceberg(location) => {
+                let table = opts.table.as_deref();
+                read_iceberg(&IcebergTable::try_new(location, table, &opts.snapshot)?)?
+            }
+            DataSetConn::Excel(filename) => {
+                let batch = read_excel(filename, &opts.excel)?;
+                from_batches(&batch.schema(), [Ok(batch)].into_iter())?
+            }
+            DataSetConn::Csv(file_opts) => read_csv(file_opts, &opts.csv, opts.schema.as_ref())?,
+            DataSetConn::FixedWidth(file_opts) => {
+                let layout = opts
+                    .layout
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("--layout is required for fixed-width files"))?;
+                let (schema, batches) = read_fixed_width(file_opts, layout)?;
+                from_batches(&schema, batches.into_iter().map(Ok))?
+            }
+            DataSetConn::Log(file_opts) => {
+                let regex = opts
+                    .regex
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("--regex is required for log files"))?;
+                let (schema, batches) = read_log(file_opts, regex, opts.schema.as_ref())?;
+                from_batches(&schema, batches.into_iter().map(Ok))?
+            }
+            DataSetConn::Xml(file_opts) => {
+                let (schema, batches) = read_xml(file_opts, &opts.xml, opts.schema.as_ref())?;
+                from_batches(&schema, batches.into_iter().map(Ok))?
+            }
+            DataSetConn::Json(file_opts) if is_json_document(file_opts, &opts.json)? => {
+                let (schema, batches) =
+                    read_json_document(file_opts, &opts.json, opts.schema.as_ref())?;
+                from_batches(&schema, batches.into_iter().map(Ok))?
+            }
+            DataSetConn::Json(file_opts) => read_ndjson(file_opts, opts.schema.as_ref())?,
+        };
+        self.0.register(&opts.name, lf);
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<impl ReplDisplay> {
+        let mut tables = self.0.get_tables();
+        tables.sort();
+        let table_type = vec!["BASE TABLE"; tables.len()];
+        let df = df!(
+            "table_name" => tables,
+            "table_type" => table_type,
+        )?;
+        Ok(df)
+    }
+
+    async fn schema(&self, opts: SchemaOpts) -> anyhow::Result<impl ReplDisplay> {
+        let schema = self.table(&opts.name)?.collect_schema()?;
+        let (names, types): (Vec<_>, Vec<_>) = schema
+            .iter()
+            .map(|(name, dt)| (name.to_string(), dt.to_string()))
+            .unzip();
+        let nullable = vec!["YES"; names.len()];
+        let df = df!(
+            "column_name" => names,
+            "data_type" => types,
+            "is_nullable" => nullable,
+        )?;
+        Ok(df)
+    }
+
+    async fn describe(&self, opts: DescribeOpts) -> anyhow::Result<impl ReplDisplay> {
+        let lf = self.table(&opts.name)?;
+        describe(lf)
+    }
+
+    async fn head(&self, opts: HeadOpts) -> anyhow::Result<impl ReplDisplay> {
+        let df = self
+            .table(&opts.name)?
+            .limit(opts.size.unwrap_or(5) as IdxSize)
+            .collect()?;
+        Ok(df)
+    }
+
+    async fn sql(&self, opts: SqlOpts) -> anyhow::Result<impl ReplDisplay> {
+        let mut ctx = self.0.clone();
+        let df = ctx.execute(&opts.sql)?.collect()?;
+        Ok(df)
+    }
+
+    async fn export(&self, opts: ExportOpts) -> anyhow::Result<usize> {
+        let mut ctx = self.0.clone();
+        let df = ctx.execute(&opts.sql())?.collect()?;
+
+        // hand the result over to arrow so files are written the same way by both backends
+        let (schema, batches) = to_batches(df)?;
+        let table = MemTable::try_new(schema, vec![batches])?;
+        let df = SessionContext::new().read_table(Arc::new(table))?;
+        write_dataframe(df, &opts.target).await
+    }
+}
+
+/// Frames are printed by arrow like the DataFusion backend does, with every row and column
+/// instead of the few polars shows
+impl ReplDisplay for DataFrame {
+    async fn display(self) -> anyhow::Result<String> {
+        let (_, batches) = to_batches(self)?;
+        Ok(pretty_format_batches(&batches)?.to_string())
+    }
+}
+
+fn to_batches(mut df: DataFrame) -> anyhow::Result<(datatypes::SchemaRef, Vec<RecordBatch>)> {
+    let mut ipc = vec![];
+    IpcWriter::new(&mut ipc)
+        .with_compat_level(CompatLevel::oldest())
+        .finish(&mut df)?;
+    let reader = FileReader::try_new(Cursor::new(ipc), None)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// Hive partitions are only read from directories by default, globs enable them as well.
+/// Their values are strings like in the DataFusion backend instead of inferred numbers or dates
+async fn hive_options(file_opts: &FileOpts) -> anyhow::Result<HiveOptions> {
+    if !is_listing(&file_opts.filename) {
+        return Ok(HiveOptions::default());
+    }
+    let names = partition_names(&SessionContext::new(), file_opts).await?;
+    let schema = names
+        .into_iter()
+        .map(|name| Field::new(name.into(), DataType::String))
+        .collect::<Schema>();
+    Ok(HiveOptions {
+        enabled: Some(true),
+        schema: Some(Arc::new(schema)),
+        try_parse_dates: false,
+        ..Default::default()
+    })
+}
+
+fn open(file_opts: &FileOpts) -> anyhow::Result<Box<dyn Read + Send>> {
+    if is_listing(&file_opts.filename) {
+        return Err(anyhow!(
+            "directories and globs of {} files are not supported by the polars backend",
+            file_opts.ext
+        ));
+    }
+    let file = File::open(&file_opts.filename)?;
+    Ok(file_opts.compression.convert_read(file)?)
+}
+
+fn read_csv(
+    file_opts: &FileOpts,
+    csv: &CsvOpts,
+    schema: Option<&datatypes::SchemaRef>,
+) -> anyhow::Result<LazyFrame> {
+    if csv.escape.is_some() {
+        return Err(anyhow!("--escape is not supported by the polars backend"));
+    }
+    let separator = csv.delimiter.unwrap_or(b',');
+    let quote_char = Some(csv.quote.unwrap_or(b'"'));
+    let comment_prefix = csv
+        .comment
+        .map(|c| PlSmallStr::from((c as char).to_string()));
+    let null_values = csv
+        .null_value
+        .as_ref()
+        .map(|v| NullValues::AllColumnsSingle(v.into()));
+    let infer_schema_length = Some(csv.schema_infer_rows.unwrap_or(100));
+    let eol_char = csv.terminator.unwrap_or(b'\n');
+    let schema = schema.map(polars_schema).transpose()?;
+    // the csv reader doesn't parse decimals and times, they're read as strings and cast
+    let casts = schema
+        .iter()
+        .flat_map(|schema| schema.iter())
+        .filter_map(|(name, dt)| match dt {
+            DataType::Decimal(_, _) => Some(col(name.clone()).strict_cast(dt.clone())),
+            DataType::Time => Some(col(name.clone()).str().to_time(StrptimeOptions {
+                format: Some("%H:%M:%S%.f".into()),
+                ..Default::default()
+            })),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let schema = schema.map(|schema| {
+        let strings = schema.iter().map(|(name, dt)| match dt {
+            DataType::Decimal(_, _) | DataType::Time => Field::new(name.clone(), DataType::String),
+            dt => Field::new(name.clone(), dt.clone()),
+        });
+        Arc::new(strings.collect::<Schema>())
+    });
+
+    let lf = if !file_opts.compression.is_compressed() {
+        LazyCsvReader::new(&file_opts.filename)
+            .with_has_header(!csv.no_header)
+            .with_separator(separator)
+            .with_quote_char(quote_char)
+            .with_eol_char(eol_char)
+            .with_comment_prefix(comment_prefix)
+            .with_null_values(null_values)
+            .with_infer_schema_length(infer_schema_length)
+            .with_schema(schema)
+            .finish()?
+    } else {
+        // compressed files can't be scanned lazily, decompress them into memory first
+        let mut buf = vec![];
+        open(file_opts)?.read_to_end(&mut buf)?;
+        CsvReadOptions::default()
+            .with_has_header(!csv.no_header)
+            .with_infer_schema_length(infer_schema_length)
+            .with_schema(schema)
+            .map_parse_options(|opts| {
+                opts.with_separator(separator)
+                    .with_quote_char(quote_char)
+                    .with_eol_char(eol_char)
+                    .with_comment_prefix(comment_prefix.as_deref())
+                    .with_null_values(null_values.clone())
+            })
+            .into_reader_with_file_handle(Cursor::new(buf))
+            .finish()?
+            .lazy()
+    };
+    Ok(match casts.is_empty() {
+        true => lf,
+        false => lf.with_columns(casts),
+    })
+}
+
+/// polars is built without its json reader, so ndjson is decoded by arrow
+fn read_ndjson(
+    file_opts: &FileOpts,
+    schema: Option<&datatypes::SchemaRef>,
+) -> anyhow::Result<LazyFrame> {
+    let mut buf = vec![];
+    open(file_opts)?.read_to_end(&mut buf)?;
+    let mut reader = BufReader::new(Cursor::new(buf));
+    let schema = match schema {
+        Some(schema) => schema.clone(),
+        None => Arc::new(infer_json_schema(&mut reader, None)?.0),
+    };
+    reader.rewind()?;
+    let json = ReaderBuilder::new(schema.clone()).build(reader)?;
+    from_batches(&schema, json.map(|b| b.map_err(Into::into)))
+}
+
+fn read_avro(filename: &str) -> anyhow::Result<LazyFrame> {
+    let file = BufReader::new(File::open(filename)?);
+    let reader = avro_to_arrow::ReaderBuilder::new()
+        .read_schema()
+        .build(file)?;
+    from_batches(&reader.schema(), reader.map(|b| b.map_err(Into::into)))
+}
+
+fn read_orc(filename: &str) -> anyhow::Result<LazyFrame> {
+    let file = OrcFile::try_new(filename)?;
+    let mut batches = vec![];
+    for stripe in 0..file.num_stripes() {
+        batches.extend(file.read_stripe(stripe, None)?);
+    }
+    from_batches(&file.schema(), batches.into_iter())
+}
+
+/// Every data file is scanned on its own, with its partition values added as literals
+fn read_delta(table: &DeltaTable) -> anyhow::Result<LazyFrame> {
+    let partition_schema = polars_schema(&table.partition_schema())?;
+    let frames = table
+        .files()
+        .iter()
+        .map(|file| {
+            let partitions = partition_schema
+                .iter()
+                .map(|(name, dtype)| {
+                    let value = match file.partition_value(name) {
+                        Some(value) => lit(value),
+                        None => lit(NULL),
+                    };
+                    value.cast(dtype.clone()).alias(name.clone())
+                })
+                .collect::<Vec<_>>();
+            let lf = LazyFrame::scan_parquet(&file.path, ScanArgsParquet::default())?;
+            Ok(lf.with_columns(partitions))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if frames.is_empty() {
+        let schema = polars_schema(&table.schema())?;
+        return Ok(DataFrame::empty_with_schema(&schema).lazy());
+    }
+    Ok(concat(frames, UnionArgs::default())?)
+}
+
+/// The columns of every file are selected by field id as the table columns, those added
+/// since a file was written are null
+fn read_iceberg(table: &IcebergTable) -> anyhow::Result<LazyFrame> {
+    let schema = table.schema();
+    let mut frames = vec![];
+    for file in table.files() {
+        let file_schema = file.schema()?;
+        let frame = LazyFrame::scan_parquet(&file.path, ScanArgsParquet::default())?;
+        let columns = file_schema
+            .fields()
+            .iter()
+            .filter_map(|f| Some((table.table_column(f)?, f.name().as_str())))
+            .collect::<HashMap<_, _>>();
+        if columns.len() == schema.fields().len() && !table.is_renamed(&file_schema) {
+            frames.push(frame);
+            continue;
+        }
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| match columns.get(f.name().as_str()) {
+                Some(column) => Ok(col(*column).alias(f.name())),
+                None => {
+                    let dtype = polars_schema(&Arc::new(datatypes::Schema::new(vec![f.clone()])))?;
+                    let dtype = dtype.get(f.name()).cloned().unwrap_or(DataType::Null);
+                    Ok(lit(NULL).cast(dtype).alias(f.name()))
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        frames.push(frame.select(columns));
+    }
+    if frames.is_empty() {
+        let schema = polars_schema(&schema)?;
+        return Ok(DataFrame::empty_with_schema(&schema).lazy());
+    }
+    Ok(concat(frames, UnionArgs::default())?)
+}
+
+fn read_sqlite(path: &str, table: &str) -> anyhow::Result<LazyFrame> {
+    let table = SqliteTable::try_new(path, table)?;
+    let projection = (0..table.schema().fields().len()).collect::<Vec<_>>();
+    let mut batches = vec![];
+    table.read(&projection, None, |batch| {
+        batches.push(Ok(batch));
+        true
+    })?;
+    from_batches(&table.schema(), batches.into_iter())
+}
+
+/// The polars counterpart of a `--schema`, only types polars is built with are supported
+fn polars_schema(schema: &datatypes::SchemaRef) -> anyhow::Result<Schema> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| {
+            let dtype = match f.data_type() {
+                datatypes::DataType::Int8 => DataType::Int8,
+                datatypes::DataType::Int16 => DataType::Int16,
+                datatypes::DataType::Int32 => DataType::Int32,
+                datatypes::DataType::Int64 => DataType::Int64,
+                datatypes::DataType::Float32 => DataType::Float32,
+                datatypes::DataType::Float64 => DataType::Float64,
+                datatypes::DataType::Utf8 => DataType::String,
+                datatypes::DataType::Boolean => DataType::Boolean,
+                datatypes::DataType::Date32 => DataType::Date,
+                datatypes::DataType::Timestamp(_, _) => {
+                    DataType::Datetime(TimeUnit::Nanoseconds, None)
+                }
+                datatypes::DataType::Time64(_) => DataType::Time,
+                // decimals come with the sql feature
+                datatypes::DataType::Decimal128(precision, scale) => {
+                    DataType::Decimal(Some(*precision as usize), Some(*scale as usize))
+                }
+                datatypes::DataType::Binary => DataType::Binary,
+                t => return Err(anyhow!("type {} is not supported by the polars backend", t)),
+            };
+            Ok(Field::new(f.name().into(), dtype))
+        })
+        .collect()
+}
+
+/// Hand arrow batches over to polars as an in-memory IPC file
+fn from_batches(
+    schema: &datatypes::Schema,
+    batches: impl Iterator<Item = anyhow::Result<RecordBatch>>,
+) -> anyhow::Result<LazyFrame> {
+    let mut writer = FileWriter::try_new(Vec::new(), schema)?;
+    for batch in batches {
+        writer.write(&batch?)?;
+    }
+    let ipc = writer.into_inner()?;
+    let df = IpcReader::new(Cursor::new(ipc)).finish()?;
+    Ok(df.lazy())
+}
+
+type Aggregation = fn(Expr) -> Expr;
+
+/// Same statistics as the DataFusion describer: temporal columns are described by their
+/// physical value, lists by their length and other non numeric columns by their string length
+fn describe(lf: LazyFrame) -> anyhow::Result<DataFrame> {
+    let schema = lf.clone().collect_schema()?;
+    let transformed = schema
+        .iter()
+        .map(|(name, dt)| {
+            let c = col(name.clone());
+            let e = match dt {
+                t if t.is_numeric() => c,
+                t if t.is_temporal() => c.to_physical(),
+                DataType::List(_) => c.list().len(),
+                _ => c.cast(DataType::String).str().len_chars(),
+            };
+            e.cast(DataType::Float64).alias(name.clone())
+        })
+        .collect::<Vec<_>>();
+    let lf = lf.select(transformed);
+
+    let aggregators: [(&str, Aggregation); 8] = [
+        ("count", |e| e.count()),
+        ("null_count", |e| e.null_count()),
+        ("mean", |e| e.mean()),
+        ("stddev", |e| e.std(1)),
+        ("min", |e| e.min()),
+        ("max", |e| e.max()),
+        ("median", |e| e.median()),
+        ("percentile(25)", |e| {
+            e.quantile(lit(0.25), QuantileMethod::Nearest)
+        }),
+    ];
+    let frames = aggregators
+        .iter()
+        .map(|(name, agg)| {
+            let mut exprs = vec![lit(*name).alias("describe")];
+            exprs.extend(
+                schema
+                    .iter_names()
+                    .map(|n| agg(col(n.clone())).cast(DataType::Float64)),
+            );
+            lf.clone().select(exprs)
+        })
+        .collect::<Vec<_>>();
+    let df = concat(frames, UnionArgs::default())?.collect()?;
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::connect::conn_parser;
+
+    #[tokio::test]
+    async fn polars_backend_should_display_every_row_and_column() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("wide.csv");
+        let header = (1..=12).map(|c| format!("c{}", c)).collect::<Vec<_>>();
+        let rows = (1..=30).map(|r| {
+            (1..=12)
+                .map(|c| (r * 100 + c).to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        });
+        let csv = std::iter::once(header.join(",")).chain(rows);
+        std::fs::write(&path, csv.collect::<Vec<_>>().join("\n"))?;
+
+        let mut backend = PolarsBackend::new();
+        let conn = conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let opts = ConnectOpts::new(conn, None, "wide".to_string());
+        backend.connect(&opts).await?;
+
+        let sql = SqlOpts::new("SELECT * FROM wide WHERE c1 > 2000".to_string());
+        let out = backend.sql(sql).await?.display().await?;
+        assert_eq!(out.lines().count(), 11 + 4);
+        assert!(out.lines().nth(1).unwrap().ends_with("| c12  |"));
+        assert!(out.contains("| 3001 |") && out.contains("| 3012 |"));
+
+        let head = HeadOpts::new("wide".to_string(), None);
+        let out = backend.head(head).await?.display().await?;
+        assert_eq!(out.lines().count(), 5 + 4);
+        assert!(std::env::var("POLARS_FMT_MAX_ROWS").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn polars_backend_should_read_csv_with_every_schema_type() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("types.csv");
+        std::fs::write(
+            &path,
+            "a,b,c,d,e,f,g,h,i,j,k\n\
+             1,2,3,4,1.5,2.5,12.34,x,true,2024-05-01,10:30:00\n\
+             -1,-2,-3,-4,0,0,,y,false,2024-05-02,23:59:59.5\n",
+        )?;
+        let schema = "a:tinyint,b:int16,c:int,d:bigint,e:float,f:double,g:decimal(10,2),\
+                      h:string,i:bool,j:date,k:time";
+
+        let mut backend = PolarsBackend::new();
+        let conn = conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let mut opts = ConnectOpts::new(conn, None, "types".to_string());
+        opts.schema = Some(Arc::new(crate::backend::schema::parse_schema(schema)?));
+        backend.connect(&opts).await?;
+
+        let types = backend.table("types")?.collect_schema()?;
+        let types = types
+            .iter()
+            .map(|(_, dt)| dt.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                "i8",
+                "i16",
+                "i32",
+                "i64",
+                "f32",
+                "f64",
+                "decimal[10,2]",
+                "str",
+                "bool",
+                "date",
+                "time"
+            ]
+        );
+        let sql = SqlOpts::new("SELECT a + b AS ab, g, k FROM types".to_string());
+        let out = backend.sql(sql).await?.display().await?;
+        assert!(out.contains("| 3  | 12.34 | 10:30:00     |"), "{}", out);
+        assert!(out.contains("| -3 |       | 23:59:59.500 |"), "{}", out);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn partition_columns_should_be_strings_in_both_backends() -> anyhow::Result<()> {
+        use crate::backend::df::DataFusionBackend;
+        use datafusion::arrow::array::Int64Array;
+        use datafusion::parquet::arrow::ArrowWriter;
+
+        let dir = tempfile::tempdir()?;
+        let schema = Arc::new(datatypes::Schema::new(vec![datatypes::Field::new(
+            "id",
+            datatypes::DataType::Int64,
+            false,
+        )]));
+        for (i, part) in ["year=2023/month=12", "year=2024/month=01"]
+            .iter()
+            .enumerate()
+        {
+            let path = dir.path().join(part);
+            std::fs::create_dir_all(&path)?;
+            let file = File::create(path.join("part-0.parquet"))?;
+            let ids = Arc::new(Int64Array::from(vec![i as i64]));
+            let batch = RecordBatch::try_new(schema.clone(), vec![ids])?;
+            let mut writer = ArrowWriter::try_new(file, schema.clone(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+        }
+        let conn = conn_parser(&dir.path().to_string_lossy()).map_err(|e| anyhow!(e))?;
+        let opts = ConnectOpts::new(conn, None, "events".to_string());
+        let sql = "SELECT id, year, month FROM events WHERE month = '01'";
+
+        let mut polars = PolarsBackend::new();
+        polars.connect(&opts).await?;
+        let types = polars.table("events")?.collect_schema()?;
+        let types = types.iter().map(|(n, dt)| format!("{}: {}", n, dt));
+        assert_eq!(
+            types.collect::<Vec<_>>(),
+            ["id: i64", "year: str", "month: str"]
+        );
+        let mut datafusion = DataFusionBackend::new();
+        datafusion.connect(&opts).await?;
+        let types = datafusion.table("events").await?.schema().clone();
+        let types = types.fields().iter().map(|f| f.data_type().to_string());
+        assert_eq!(types.collect::<Vec<_>>(), ["Int64", "Utf8", "Utf8"]);
+
+        let expected = "+----+------+-------+\n\
+                        | id | year | month |\n\
+                        +----+------+-------+\n\
+                        | 1  | 2024 | 01    |\n\
+                        +----+------+-------+";
+        let out = polars.sql(SqlOpts::new(sql.to_string())).await?;
+        assert_eq!(out.display().await?, expected);
+        let out = datafusion.sql(SqlOpts::new(sql.to_string())).await?;
+        assert_eq!(out.display().await?, expected);
+        Ok(())
+    }
+}
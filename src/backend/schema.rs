@@ -0,0 +1,247 @@
+use anyhow::anyhow;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::sql::sqlparser::ast::{ColumnOption, Statement};
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::Parser;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A column of a JSON schema file
+#[derive(Debug, Deserialize)]
+struct JsonField {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    #[serde(default = "default_nullable")]
+    nullable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonSchema {
+    Fields(Vec<JsonField>),
+    Object { fields: Vec<JsonField> },
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+/// Parse a schema given inline as `col:type,...` or as the path of a schema file, either
+/// JSON (`[{"name": "zip", "type": "string"}]`) or a `CREATE TABLE` statement
+pub fn parse_schema(spec: &str) -> anyhow::Result<Schema> {
+    if !Path::new(spec).is_file() {
+        // an inline schema has a type for every column and never a path separator
+        if !spec.contains(':') || spec.contains(['/', '\\']) {
+            return Err(anyhow!("schema file {} not found", spec));
+        }
+        return inline_schema(spec);
+    }
+    let content = std::fs::read_to_string(spec)?;
+    let content = content.trim();
+    match content.starts_with(['[', '{']) {
+        true => json_schema(content),
+        false => ddl_schema(content),
+    }
+    .map_err(|e| anyhow!("invalid schema file {}: {}", spec, e))
+}
+
+fn inline_schema(spec: &str) -> anyhow::Result<Schema> {
+    let fields = split_top_level(spec)
+        .into_iter()
+        .map(|column| {
+            let (name, data_type) = column
+                .split_once(':')
+                .ok_or_else(|| anyhow!("invalid column {}, expected name:type", column))?;
+            Ok(Field::new(name.trim(), parse_type(data_type)?, true))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+fn json_schema(content: &str) -> anyhow::Result<Schema> {
+    let fields = match serde_json::from_str(content)? {
+        JsonSchema::Fields(fields) | JsonSchema::Object { fields } => fields,
+    };
+    let fields = fields
+        .into_iter()
+        .map(|f| Ok(Field::new(f.name, parse_type(&f.data_type)?, f.nullable)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// A `CREATE TABLE` statement, or just its column definitions
+fn ddl_schema(content: &str) -> anyhow::Result<Schema> {
+    let content = content.trim_end_matches(';');
+    let sql = match content.to_lowercase().starts_with("create") {
+        true => content.to_string(),
+        false => format!("CREATE TABLE t ({})", content),
+    };
+    let statements = Parser::parse_sql(&GenericDialect {}, &sql)?;
+    let columns = match statements.as_slice() {
+        [Statement::CreateTable(table)] => &table.columns,
+        _ => return Err(anyhow!("expected a single CREATE TABLE statement")),
+    };
+    let fields = columns
+        .iter()
+        .map(|c| {
+            let not_null = c
+                .options
+                .iter()
+                .any(|o| matches!(o.option, ColumnOption::NotNull));
+            let data_type = parse_type(&c.data_type.to_string())?;
+            Ok(Field::new(c.name.value.clone(), data_type, !not_null))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// SQL and arrow type names, SQL types are mapped the way DataFusion maps them, so `int8`
+/// is the postgres alias of `bigint` and timestamps with a time zone are in UTC
+pub fn parse_type(s: &str) -> anyhow::Result<DataType> {
+    let s = s.trim().to_lowercase();
+    let (s, time_zone) = match s.strip_suffix("with time zone") {
+        Some(s) => (s.trim_end(), true),
+        None => (s.trim_end_matches("without time zone").trim_end(), false),
+    };
+    let (name, args) = match s.split_once('(') {
+        Some((name, args)) => (name.trim(), args.trim_end_matches(')')),
+        None => (s, ""),
+    };
+    let data_type = match name {
+        "timestamp" | "timestamptz" if time_zone || name == "timestamptz" => {
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into()))
+        }
+        _ if time_zone => return Err(anyhow!("unsupported type {} with time zone", s)),
+        "tinyint" => DataType::Int8,
+        "smallint" | "int2" | "int16" => DataType::Int16,
+        "int" | "integer" | "int4" | "int32" => DataType::Int32,
+        "bigint" | "int8" | "int64" | "long" => DataType::Int64,
+        "float" | "real" | "float32" => DataType::Float32,
+        "double" | "double precision" | "float64" => DataType::Float64,
+        "decimal" | "numeric" if args.is_empty() => DataType::Decimal128(38, 10),
+        "decimal" | "numeric" => {
+            let params = args
+                .split(',')
+                .map(|a| a.trim().parse::<u8>())
+                .collect::<Result<Vec<_>, _>>();
+            match params.as_deref() {
+                Ok([p]) if *p <= 38 => DataType::Decimal128(*p, 0),
+                Ok([p, scale]) if *p <= 38 && scale <= p => DataType::Decimal128(*p, *scale as i8),
+                _ => return Err(anyhow!("invalid decimal type {}", s)),
+            }
+        }
+        "string" | "text" | "varchar" | "char" | "character varying" | "utf8" => DataType::Utf8,
+        "bool" | "boolean" => DataType::Boolean,
+        "date" => DataType::Date32,
+        "timestamp" | "datetime" => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        "time" => DataType::Time64(TimeUnit::Nanosecond),
+        "binary" | "varbinary" | "bytea" | "blob" => DataType::Binary,
+        _ => return Err(anyhow!("unsupported type {}", s)),
+    };
+    Ok(data_type)
+}
+
+/// Split on commas outside of parentheses, so `decimal(10,2)` is kept whole
+pub(crate) fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_type_should_map_sql_types_like_datafusion() {
+        let utc = DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into()));
+        let cases = [
+            ("tinyint", DataType::Int8),
+            ("int2", DataType::Int16),
+            ("INT4", DataType::Int32),
+            ("int8", DataType::Int64),
+            ("numeric(10, 2)", DataType::Decimal128(10, 2)),
+            ("timestamp", DataType::Timestamp(TimeUnit::Nanosecond, None)),
+            (
+                "timestamp without time zone",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+            ),
+            ("timestamp with time zone", utc.clone()),
+            ("TIMESTAMP(3) WITH TIME ZONE", utc.clone()),
+            ("timestamptz", utc),
+        ];
+        for (name, data_type) in cases {
+            assert_eq!(parse_type(name).unwrap(), data_type, "{}", name);
+        }
+        let e = parse_type("time with time zone").unwrap_err();
+        assert_eq!(e.to_string(), "unsupported type time with time zone");
+    }
+
+    #[test]
+    fn parse_schema_should_read_json_schema_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.json");
+        let json = r#"{"fields": [
+            {"name": "id", "type": "int8", "nullable": false},
+            {"name": "zip", "type": "string"}
+        ]}"#;
+        std::fs::write(&path, json).unwrap();
+
+        let schema = parse_schema(&path.to_string_lossy()).unwrap();
+        let expected = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("zip", DataType::Utf8, true),
+        ]);
+        assert_eq!(schema, expected);
+    }
+
+    #[test]
+    fn parse_schema_should_read_ddl_schema_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.sql");
+        let ddl = "CREATE TABLE sales (\n  id int4 NOT NULL,\n  total decimal(10,2),\n  \
+                   at timestamp with time zone\n);\n";
+        std::fs::write(&path, ddl).unwrap();
+
+        let schema = parse_schema(&path.to_string_lossy()).unwrap();
+        let expected = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("total", DataType::Decimal128(10, 2), true),
+            Field::new(
+                "at",
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into())),
+                true,
+            ),
+        ]);
+        assert_eq!(schema, expected);
+
+        // the column definitions alone are read as the columns of a table
+        std::fs::write(&path, "id bigint, name varchar(20)").unwrap();
+        let schema = parse_schema(&path.to_string_lossy()).unwrap();
+        let types = schema.fields().iter().map(|f| f.data_type().clone());
+        assert_eq!(types.collect::<Vec<_>>(), [DataType::Int64, DataType::Utf8]);
+    }
+
+    #[test]
+    fn parse_schema_should_report_missing_schema_files() {
+        for spec in ["schemas/sales.json", "sales.sql", "./a:int.json"] {
+            let e = parse_schema(spec).unwrap_err();
+            assert_eq!(e.to_string(), format!("schema file {} not found", spec));
+        }
+        let schema = parse_schema("id:int8, total:decimal(10,2)").unwrap();
+        assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(10, 2));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::cli::connect::{CsvOpts, FileOpts};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Read;
+
+/// Number of bytes read from the start of the file to guess its dialect
+const SAMPLE_SIZE: u64 = 16 * 1024;
+const DELIMITERS: [u8; 5] = [b',', b';', b'\t', b'|', b':'];
+const QUOTES: [u8; 2] = [b'"', b'\''];
+
+type Record = Vec<String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+/// The dialect a CSV file is read with, explicit options take precedence over sniffed values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialect {
+    pub delimiter: u8,
+    pub quote: u8,
+    pub has_header: bool,
+    pub terminator: Terminator,
+}
+
+impl Terminator {
+    /// The byte given to the csv readers, both of them handle `\r\n` by default
+    pub fn byte(&self) -> Option<u8> {
+        match self {
+            Terminator::Cr => Some(b'\r'),
+            Terminator::Lf | Terminator::CrLf => None,
+        }
+    }
+}
+
+/// Guess the dialect of a CSV file from its first few KB and fill in the options
+/// that weren't given explicitly
+pub fn sniff_csv(file_opts: &FileOpts, csv: &mut CsvOpts) -> anyhow::Result<Dialect> {
+    let file = File::open(file_opts.sample()?)?;
+    let mut sample = vec![];
+    file_opts
+        .compression
+        .convert_read(file)?
+        .take(SAMPLE_SIZE)
+        .read_to_end(&mut sample)?;
+    let truncated = sample.len() as u64 == SAMPLE_SIZE;
+
+    let dialect = sniff(&sample, truncated, csv);
+    csv.delimiter = Some(dialect.delimiter);
+    csv.quote = Some(dialect.quote);
+    csv.no_header = !dialect.has_header;
+    csv.header = false;
+    csv.terminator = dialect.terminator.byte();
+    Ok(dialect)
+}
+
+fn sniff(sample: &[u8], truncated: bool, csv: &CsvOpts) -> Dialect {
+    let terminator = sniff_terminator(sample);
+    let eol = match terminator {
+        Terminator::Cr => b'\r',
+        Terminator::Lf | Terminator::CrLf => b'\n',
+    };
+    let delimiters = csv
+        .delimiter
+        .map(|d| vec![d])
+        .unwrap_or(DELIMITERS.to_vec());
+    let quotes = csv.quote.map(|q| vec![q]).unwrap_or(QUOTES.to_vec());
+
+    // records with a consistent field count decide, then the number of quoted fields,
+    // the first candidate wins ties so `,` and `"` are kept when nothing else fits better
+    let (_, delimiter, quote, records) = delimiters
+        .iter()
+        .flat_map(|&d| quotes.iter().map(move |&q| (d, q)))
+        .map(|(delimiter, quote)| {
+            let (mut records, quoted) = split_records(sample, delimiter, quote, eol, csv.comment);
+            if truncated && records.len() > 1 {
+                records.pop();
+            }
+            ((consistency(&records), quoted), delimiter, quote, records)
+        })
+        .rev()
+        .max_by_key(|(score, ..)| *score)
+        .expect("at least one candidate");
+
+    let has_header = if csv.no_header {
+        false
+    } else if csv.header {
+        true
+    } else {
+        sniff_header(&records)
+    };
+    Dialect {
+        delimiter,
+        quote,
+        has_header,
+        terminator,
+    }
+}
+
+fn sniff_terminator(sample: &[u8]) -> Terminator {
+    match sample.iter().position(|b| *b == b'\r' || *b == b'\n') {
+        Some(i) if sample[i] == b'\r' && sample.get(i + 1) == Some(&b'\n') => Terminator::CrLf,
+        Some(i) if sample[i] == b'\r' => Terminator::Cr,
+        _ => Terminator::Lf,
+    }
+}
+
+/// Split the sample into records of fields, quoted fields may contain delimiters and newlines.
+/// The number of fields starting with a quote is returned as well
+fn split_records(
+    sample: &[u8],
+    delimiter: u8,
+    quote: u8,
+    eol: u8,
+    comment: Option<u8>,
+) -> (Vec<Record>, usize) {
+    let mut records = vec![];
+    let mut quoted = 0;
+    let mut record = vec![];
+    let mut field = vec![];
+    let mut in_quotes = false;
+    let mut at_record_start = true;
+    let mut skip_line = false;
+
+    for &b in sample {
+        if at_record_start {
+            at_record_start = false;
+            skip_line = comment == Some(b);
+        }
+        if skip_line {
+            if b == eol {
+                skip_line = false;
+                at_record_start = true;
+            }
+            continue;
+        }
+        match b {
+            b if b == quote => {
+                if !in_quotes && field.is_empty() {
+                    quoted += 1;
+                }
+                in_quotes = !in_quotes
+            }
+            b if in_quotes => field.push(b),
+            b if b == delimiter => finish_field(&mut field, &mut record),
+            b if b == eol => {
+                finish_field(&mut field, &mut record);
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                at_record_start = true;
+            }
+            b'\r' => {}
+            b => field.push(b),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        finish_field(&mut field, &mut record);
+        records.push(record);
+    }
+    (records, quoted)
+}
+
+fn finish_field(field: &mut Vec<u8>, record: &mut Record) {
+    record.push(String::from_utf8_lossy(field).trim().to_string());
+    field.clear();
+}
+
+/// Number of records having the most common field count, a single column scores nothing
+/// unless every candidate does
+fn consistency(records: &[Record]) -> usize {
+    let mut counts = std::collections::HashMap::new();
+    for record in records {
+        *counts.entry(record.len()).or_insert(0usize) += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(fields, _)| *fields > 1)
+        .map(|(_, n)| n)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The first record is taken as a header unless one of its values looks like data, i.e. it's
+/// empty, a number or repeated. Otherwise columns vote like in Python's sniffer: a column of
+/// numbers or of values of a fixed length votes for a header when its first value isn't alike,
+/// one whose first value is found again votes against. Ties keep the header, most files have one
+fn sniff_header(records: &[Record]) -> bool {
+    let Some((first, rest)) = records.split_first() else {
+        return true;
+    };
+    let mut names = HashSet::new();
+    if first
+        .iter()
+        .any(|v| v.is_empty() || v.parse::<f64>().is_ok() || !names.insert(v))
+    {
+        return false;
+    }
+    let mut votes = 0;
+    for (i, name) in first.iter().enumerate() {
+        let values = rest
+            .iter()
+            .filter_map(|record| record.get(i))
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+        let Some(len) = values.first().map(|v| v.chars().count()) else {
+            continue;
+        };
+        if values.contains(&name) {
+            votes -= 1;
+        } else if values.iter().all(|v| v.parse::<f64>().is_ok()) {
+            votes += 1;
+        } else if values.iter().all(|v| v.chars().count() == len) {
+            votes += if name.chars().count() == len { -1 } else { 1 };
+        }
+    }
+    votes >= 0
+}
+
+impl Display for Dialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "delimiter {:?}, quote {:?}, {}, line terminator {}",
+            self.delimiter as char,
+            self.quote as char,
+            if self.has_header {
+                "header"
+            } else {
+                "no header"
+            },
+            self.terminator
+        )
+    }
+}
+
+impl Display for Terminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Lf => write!(f, "'\\n'"),
+            Terminator::CrLf => write!(f, "'\\r\\n'"),
+            Terminator::Cr => write!(f, "'\\r'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialect(sample: &str) -> Dialect {
+        sniff(sample.as_bytes(), false, &CsvOpts::default())
+    }
+
+    #[test]
+    fn sniff_should_guess_delimiter_and_quote() {
+        let d = dialect("name;note\n\"a\";\"x, y\"\n\"b\";\"z\"\n");
+        assert_eq!((d.delimiter, d.quote), (b';', b'"'));
+        let d = dialect("id\tname\n1\tAda\n2\tBob\n");
+        assert_eq!(d.delimiter, b'\t');
+        let d = dialect("id|name|city\n1|Ada|London\n2|Bob|Paris\n");
+        assert_eq!(d.delimiter, b'|');
+        let d = dialect("id,note\n1,'a, b'\n2,'c, d'\n");
+        assert_eq!((d.delimiter, d.quote), (b',', b'\''));
+        // a single column keeps the defaults
+        let d = dialect("name\nAda\nBob\n");
+        assert_eq!((d.delimiter, d.quote), (b',', b'"'));
+    }
+
+    #[test]
+    fn sniff_should_guess_the_terminator() {
+        assert_eq!(dialect("a,b\r\n1,2\r\n").terminator, Terminator::CrLf);
+        assert_eq!(dialect("a,b\r1,2\r").terminator, Terminator::Cr);
+        assert_eq!(dialect("a,b\n1,2\n").terminator, Terminator::Lf);
+        // the last record of a truncated sample may be cut anywhere
+        let d = sniff(b"a;b\n1;2\n3;4\n5", true, &CsvOpts::default());
+        assert_eq!(d.delimiter, b';');
+    }
+
+    #[test]
+    fn sniff_should_guess_the_header() {
+        assert!(dialect("id,price\n1,2.5\n2,3\n").has_header);
+        assert!(!dialect("1,2.5\n2,3\n").has_header);
+        assert!(!dialect("a,,c\nx,y,z\n").has_header);
+        assert!(dialect("name,city\nAda,London\nBob,Paris\n").has_header);
+        assert!(dialect("code,country\nFR,France\nDE,Germany\n").has_header);
+        // all text without a header, the first values are alike the others
+        assert!(!dialect("FR,France\nDE,Germany\nIT,Italy\n").has_header);
+        assert!(!dialect("Ada,London\nBob,Paris\nAda,Rome\n").has_header);
+        assert!(!dialect("yes,yes\nno,yes\n").has_header);
+
+        let csv = CsvOpts {
+            header: true,
+            ..CsvOpts::default()
+        };
+        assert!(sniff(b"1,2\n3,4\n", false, &csv).has_header);
+        let csv = CsvOpts {
+            no_header: true,
+            ..CsvOpts::default()
+        };
+        assert!(!sniff(b"id,name\n1,Ada\n", false, &csv).has_header);
+    }
+}
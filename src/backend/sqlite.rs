@@ -0,0 +1,389 @@
+use anyhow::anyhow;
+use datafusion::arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, RecordBatch, RecordBatchOptions,
+    StringArray,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OpenFlags};
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 8192;
+
+/// How a sqlite column is decoded into arrow, chosen from the declared type with the
+/// sqlite affinity rules. Values of another storage class are converted when possible,
+/// those that can't be are read as null.
+#[derive(Debug, Clone, Copy)]
+pub enum SqliteColumn {
+    Integer,
+    Real,
+    Boolean,
+    Text,
+    Blob,
+}
+
+/// A table or view of a sqlite file, a connection is opened for every read since
+/// sqlite connections can't be shared between threads
+#[derive(Debug, Clone)]
+pub struct SqliteTable {
+    path: String,
+    table: String,
+    schema: SchemaRef,
+    columns: Vec<SqliteColumn>,
+}
+
+impl SqliteTable {
+    pub fn try_new(path: &str, table: &str) -> anyhow::Result<Self> {
+        let conn = open(path)?;
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table)))?;
+        let decls = stmt
+            .query_map([], |row| {
+                let name: String = row.get(1)?;
+                let decl: String = row.get(2)?;
+                let not_null: bool = row.get(3)?;
+                Ok((name, sqlite_column(&decl), not_null))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        if decls.is_empty() {
+            return Err(anyhow!("table {} not found in {}", table, path));
+        }
+        let mixed = mixed_columns(&conn, table, &decls)?;
+        let (fields, columns): (Vec<_>, Vec<_>) = decls
+            .into_iter()
+            .zip(mixed)
+            .map(|((name, column, not_null), mixed)| {
+                let column = if mixed { SqliteColumn::Text } else { column };
+                (Field::new(name, arrow_type(column), !not_null), column)
+            })
+            .unzip();
+
+        Ok(Self {
+            path: path.to_string(),
+            table: quote_ident(table),
+            schema: Arc::new(Schema::new(fields)),
+            columns,
+        })
+    }
+
+    /// All tables and views of the file, internal sqlite tables excluded
+    pub fn tables(path: &str) -> anyhow::Result<Vec<String>> {
+        let conn = open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type IN ('table', 'view') \
+             AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )?;
+        let tables = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(tables)
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Read the projected columns in batches, stops early when `f` returns false
+    pub fn read(
+        &self,
+        projection: &[usize],
+        limit: Option<usize>,
+        mut f: impl FnMut(RecordBatch) -> bool,
+    ) -> anyhow::Result<()> {
+        let fields = self.schema.fields();
+        let select = if projection.is_empty() {
+            "NULL".to_string()
+        } else {
+            projection
+                .iter()
+                .map(|&i| quote_ident(fields[i].name()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", select, self.table);
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let schema = Arc::new(self.schema.project(projection)?);
+        let columns = projection
+            .iter()
+            .map(|&i| self.columns[i])
+            .collect::<Vec<_>>();
+        let conn = open(&self.path)?;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+        let mut values = vec![vec![]; columns.len()];
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            for (i, v) in values.iter_mut().enumerate() {
+                v.push(row.get::<_, Value>(i)?);
+            }
+            count += 1;
+            if count == BATCH_SIZE {
+                if !f(to_batch(&schema, &columns, &mut values, count)?) {
+                    return Ok(());
+                }
+                count = 0;
+            }
+        }
+        if count > 0 {
+            f(to_batch(&schema, &columns, &mut values, count)?);
+        }
+        Ok(())
+    }
+}
+
+fn open(path: &str) -> anyhow::Result<Connection> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    Connection::open_with_flags(path, flags)
+        .map_err(|e| anyhow!("failed to open sqlite file {}: {}", path, e))
+}
+
+/// Whether columns hold values that don't convert to their type, like a real with a fraction
+/// in an integer column or a word in a numeric one. Those columns are read as text so no
+/// value is lost, at the cost of a scan of the table.
+fn mixed_columns(
+    conn: &Connection,
+    table: &str,
+    decls: &[(String, SqliteColumn, bool)],
+) -> anyhow::Result<Vec<bool>> {
+    let checks = decls
+        .iter()
+        .map(|(name, column, _)| {
+            let c = quote_ident(name);
+            let mismatch = match column {
+                SqliteColumn::Integer => format!(
+                    "typeof({c}) IN ('text', 'blob') OR \
+                     (typeof({c}) = 'real' AND {c} <> CAST({c} AS INTEGER))"
+                ),
+                SqliteColumn::Real => format!("typeof({c}) IN ('text', 'blob')"),
+                SqliteColumn::Boolean => format!(
+                    "typeof({c}) IN ('real', 'blob') OR \
+                     (typeof({c}) = 'text' AND lower({c}) NOT IN ('1', 'true', '0', 'false'))"
+                ),
+                SqliteColumn::Text | SqliteColumn::Blob => "0".to_string(),
+            };
+            format!("coalesce(max({}), 0)", mismatch)
+        })
+        .collect::<Vec<_>>();
+    let sql = format!("SELECT {} FROM {}", checks.join(", "), quote_ident(table));
+    let mixed = conn.query_row(&sql, [], |row| {
+        (0..decls.len())
+            .map(|i| row.get::<_, bool>(i))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(mixed)
+}
+
+fn to_batch(
+    schema: &SchemaRef,
+    columns: &[SqliteColumn],
+    values: &mut [Vec<Value>],
+    rows: usize,
+) -> anyhow::Result<RecordBatch> {
+    let arrays = columns
+        .iter()
+        .zip(values.iter_mut())
+        .map(|(column, values)| to_array(*column, std::mem::take(values)))
+        .collect();
+    let options = RecordBatchOptions::new().with_row_count(Some(rows));
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        arrays,
+        &options,
+    )?)
+}
+
+/// Values that don't convert, e.g. written since the columns were checked, are read as null
+fn to_array(column: SqliteColumn, values: Vec<Value>) -> ArrayRef {
+    match column {
+        SqliteColumn::Integer => {
+            Arc::new(Int64Array::from_iter(values.into_iter().map(|v| match v {
+                Value::Integer(i) => Some(i),
+                Value::Real(f) if f.fract() == 0.0 => Some(f as i64),
+                Value::Text(s) => s.trim().parse().ok(),
+                _ => None,
+            })))
+        }
+        SqliteColumn::Real => Arc::new(Float64Array::from_iter(values.into_iter().map(
+            |v| match v {
+                Value::Integer(i) => Some(i as f64),
+                Value::Real(f) => Some(f),
+                Value::Text(s) => s.trim().parse().ok(),
+                _ => None,
+            },
+        ))),
+        SqliteColumn::Boolean => Arc::new(BooleanArray::from_iter(values.into_iter().map(
+            |v| match v {
+                Value::Integer(i) => Some(i != 0),
+                Value::Text(s) => match s.to_lowercase().as_str() {
+                    "1" | "true" => Some(true),
+                    "0" | "false" => Some(false),
+                    _ => None,
+                },
+                _ => None,
+            },
+        ))),
+        SqliteColumn::Text => Arc::new(StringArray::from_iter(values.into_iter().map(
+            |v| match v {
+                Value::Null => None,
+                Value::Integer(i) => Some(i.to_string()),
+                Value::Real(f) => Some(f.to_string()),
+                Value::Text(s) => Some(s),
+                Value::Blob(b) => Some(String::from_utf8_lossy(&b).to_string()),
+            },
+        ))),
+        SqliteColumn::Blob => Arc::new(BinaryArray::from_iter(values.into_iter().map(
+            |v| match v {
+                Value::Null => None,
+                Value::Integer(i) => Some(i.to_string().into_bytes()),
+                Value::Real(f) => Some(f.to_string().into_bytes()),
+                Value::Text(s) => Some(s.into_bytes()),
+                Value::Blob(b) => Some(b),
+            },
+        ))),
+    }
+}
+
+/// Column affinity rules of sqlite, dates are kept as text since sqlite has no date type
+/// and columns without a declared type accept anything so they are read as text as well
+fn sqlite_column(decl: &str) -> SqliteColumn {
+    let decl = decl.to_uppercase();
+    match decl.as_str() {
+        d if d.contains("INT") => SqliteColumn::Integer,
+        d if d.contains("CHAR") || d.contains("CLOB") || d.contains("TEXT") || d.is_empty() => {
+            SqliteColumn::Text
+        }
+        d if d.contains("BLOB") => SqliteColumn::Blob,
+        d if d.contains("BOOL") => SqliteColumn::Boolean,
+        d if d.contains("DATE") || d.contains("TIME") => SqliteColumn::Text,
+        _ => SqliteColumn::Real,
+    }
+}
+
+fn arrow_type(column: SqliteColumn) -> DataType {
+    match column {
+        SqliteColumn::Integer => DataType::Int64,
+        SqliteColumn::Real => DataType::Float64,
+        SqliteColumn::Boolean => DataType::Boolean,
+        SqliteColumn::Text => DataType::Utf8,
+        SqliteColumn::Blob => DataType::Binary,
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+
+    /// Items priced with a word and orders of a fractional quantity, which sqlite stores as
+    /// declared but can't be read with the type of their column
+    pub(crate) fn shop(dir: &std::path::Path) -> anyhow::Result<String> {
+        let path = dir.join("shop.db").to_string_lossy().to_string();
+        let db = Connection::open(&path)?;
+        db.execute_batch(
+            "CREATE TABLE items (id INTEGER NOT NULL, name TEXT, price NUMERIC, sold BOOLEAN);
+             INSERT INTO items VALUES (1, 'pen', 1.5, 1), (2, 'ink', 'N/A', 'false'), (3, 'pad', 2, NULL);
+             CREATE TABLE orders (id INTEGER, item INTEGER, qty INTEGER);
+             INSERT INTO orders VALUES (1, 1, 2), (2, 3, 1.5);",
+        )?;
+        Ok(path)
+    }
+
+    fn read(table: &SqliteTable, projection: &[usize], limit: Option<usize>) -> String {
+        let mut batches = vec![];
+        table
+            .read(projection, limit, |batch| {
+                batches.push(batch);
+                true
+            })
+            .unwrap();
+        pretty_format_batches(&batches).unwrap().to_string()
+    }
+
+    #[test]
+    fn sqlite_table_should_read_mixed_columns_as_text() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = shop(dir.path())?;
+
+        let items = SqliteTable::try_new(&path, "items")?;
+        let types = items
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect::<Vec<_>>();
+        let expected = [
+            DataType::Int64,
+            DataType::Utf8,
+            DataType::Utf8,
+            DataType::Boolean,
+        ];
+        assert_eq!(types, expected);
+        let expected = [
+            "+----+------+-------+-------+",
+            "| id | name | price | sold  |",
+            "+----+------+-------+-------+",
+            "| 1  | pen  | 1.5   | true  |",
+            "| 2  | ink  | N/A   | false |",
+            "| 3  | pad  | 2     |       |",
+            "+----+------+-------+-------+",
+        ];
+        assert_eq!(read(&items, &[0, 1, 2, 3], None), expected.join("\n"));
+
+        let orders = SqliteTable::try_new(&path, "orders")?;
+        assert_eq!(orders.schema().field(2).data_type(), &DataType::Utf8);
+        assert_eq!(orders.schema().field(1).data_type(), &DataType::Int64);
+        Ok(())
+    }
+
+    #[test]
+    fn sqlite_table_should_read_the_projection_up_to_the_limit() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let items = SqliteTable::try_new(&shop(dir.path())?, "items")?;
+        let expected = [
+            "+------+----+",
+            "| name | id |",
+            "+------+----+",
+            "| pen  | 1  |",
+            "+------+----+",
+        ];
+        assert_eq!(read(&items, &[1, 0], Some(1)), expected.join("\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn to_array_should_read_values_that_do_not_convert_as_null() {
+        let values = vec![
+            Value::Integer(1),
+            Value::Real(2.5),
+            Value::Text(" 3 ".to_string()),
+            Value::Text("N/A".to_string()),
+        ];
+        let array = to_array(SqliteColumn::Integer, values.clone());
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None, Some(3), None]));
+        assert_eq!(&array, &expected);
+        let array = to_array(SqliteColumn::Real, values);
+        let expected: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(1.0),
+            Some(2.5),
+            Some(3.0),
+            None,
+        ]));
+        assert_eq!(&array, &expected);
+    }
+
+    #[test]
+    fn sqlite_table_should_list_tables_and_reject_unknown_ones() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = shop(dir.path())?;
+        assert_eq!(SqliteTable::tables(&path)?, ["items", "orders"]);
+        let err = SqliteTable::try_new(&path, "users").unwrap_err();
+        assert!(err.to_string().starts_with("table users not found in "));
+        Ok(())
+    }
+}
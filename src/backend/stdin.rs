@@ -0,0 +1,73 @@
+use crate::cli::connect::{conn_parser, is_file_type, DataSetConn};
+use anyhow::anyhow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+
+/// The data piped to standard input, or given inline with `--inline`, written to a file of the
+/// scratch directory and connected like a file of `format`, csv if omitted. Parquet, arrow and compressed
+/// data are told apart by their content whatever the format. The file is kept until the end
+/// of the session so the dataset can be registered again when the backend is switched
+pub fn stdin_conn(
+    scratch_dir: &Path,
+    inline: Option<&str>,
+    format: Option<&str>,
+) -> anyhow::Result<DataSetConn> {
+    let format = format.unwrap_or("csv").trim_start_matches('.');
+    if !is_file_type(format) {
+        return Err(anyhow!(
+            "invalid format {}, expected a file type like csv, json or parquet",
+            format
+        ));
+    }
+    let data = match inline {
+        Some(data) => data.as_bytes().to_vec(),
+        None => read_stdin()?,
+    };
+    if data.is_empty() {
+        return Err(anyhow!("no data to connect, the input is empty"));
+    }
+
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let dir = scratch_dir.join("stdin");
+    let path = dir.join(format!("{:016x}.{}", hasher.finish(), format));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, data)?;
+    conn_parser(&path.to_string_lossy()).map_err(|e| anyhow!(e))
+}
+
+fn read_stdin() -> anyhow::Result<Vec<u8>> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(anyhow!(
+            "standard input is a terminal, pipe the data in and run commands with --command"
+        ));
+    }
+    let mut data = vec![];
+    stdin.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stdin_conn_should_name_the_file_from_the_data() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let conn = stdin_conn(dir, Some("a,b\n1,2\n"), Some("csv")).unwrap();
+        let DataSetConn::Csv(opts) = conn else {
+            panic!("expected a csv file, got {:?}", conn);
+        };
+        let path = std::path::Path::new(&opts.filename);
+        assert_eq!(path.parent(), Some(dir.join("stdin").as_path()));
+        assert!(opts.filename.ends_with(".csv"));
+
+        assert!(stdin_conn(dir, Some("a\n1\n"), Some("../../etc/x")).is_err());
+        assert!(stdin_conn(dir, Some("a\n1\n"), Some("csv/../x")).is_err());
+        assert!(stdin_conn(dir, Some(""), None).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::anyhow;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+use object_store::http::HttpBuilder;
+use object_store::{ClientOptions, ObjectStore};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The store an object url is read through, along with the url it is registered for
+pub fn remote_store(url: &str) -> anyhow::Result<(ObjectStoreUrl, Arc<dyn ObjectStore>)> {
+    let (scheme, _) = url
+        .split_once("://")
+        .ok_or_else(|| anyhow!("invalid object url {}", url))?;
+    match scheme.to_lowercase().as_str() {
+        "s3" => s3_store(url),
+        "http" | "https" => http_store(url),
+        scheme => Err(anyhow!("unsupported object store scheme {}", scheme)),
+    }
+}
+
+/// The store of the bucket of an `s3://bucket/key` url. It is configured by the `default`
+/// profile, or the one named by `AWS_PROFILE`, of `~/.aws/config` and `~/.aws/credentials`,
+/// then by `AWS_*` environment variables which take precedence, e.g. `AWS_ENDPOINT` and
+/// `AWS_ALLOW_HTTP` for a local MinIO
+fn s3_store(url: &str) -> anyhow::Result<(ObjectStoreUrl, Arc<dyn ObjectStore>)> {
+    let bucket = url
+        .split_once("://")
+        .and_then(|(_, path)| path.split('/').next())
+        .filter(|bucket| !bucket.is_empty())
+        .ok_or_else(|| anyhow!("invalid s3 url {}, expected s3://bucket/key", url))?;
+
+    let store = s3_builder()?.with_bucket_name(bucket).build()?;
+    let store_url = ObjectStoreUrl::parse(format!("s3://{}", bucket))?;
+    Ok((store_url, Arc::new(store)))
+}
+
+fn s3_builder() -> anyhow::Result<AmazonS3Builder> {
+    let mut builder = AmazonS3Builder::new();
+    for (key, value) in profile_config()? {
+        builder = builder.with_config(key, value);
+    }
+    for (key, value) in std::env::vars() {
+        if !key.starts_with("AWS_") {
+            continue;
+        }
+        if let Ok(key) = key.to_ascii_lowercase().parse::<AmazonS3ConfigKey>() {
+            builder = builder.with_config(key, value);
+        }
+    }
+    Ok(builder)
+}
+
+/// The store of the server of an `http(s)://host/path` url. Objects are fetched with range
+/// requests, so only the footer and the needed row groups of a parquet file are downloaded
+fn http_store(url: &str) -> anyhow::Result<(ObjectStoreUrl, Arc<dyn ObjectStore>)> {
+    let (scheme, path) = url.split_once("://").unwrap_or_default();
+    let host = path
+        .split('/')
+        .next()
+        .filter(|host| !host.is_empty())
+        .ok_or_else(|| anyhow!("invalid http url {}, expected http(s)://host/path", url))?;
+    let scheme = scheme.to_lowercase();
+    let origin = format!("{}://{}", scheme, host);
+
+    // plain http is refused by default, but is how local servers and mirrors are reached
+    let options = ClientOptions::new().with_allow_http(scheme == "http");
+    let store = HttpBuilder::new()
+        .with_url(&origin)
+        .with_client_options(options)
+        .build()?;
+    Ok((ObjectStoreUrl::parse(origin)?, Arc::new(store)))
+}
+
+/// The settings of the profile in the aws config and credentials files, keys object_store
+/// doesn't know like `output` are ignored
+fn profile_config() -> anyhow::Result<Vec<(AmazonS3ConfigKey, String)>> {
+    let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+    // profiles other than the default one are prefixed in the config file only
+    let config_section = match profile.as_str() {
+        "default" => profile.clone(),
+        _ => format!("profile {}", profile),
+    };
+    let files = [
+        (config_file("AWS_CONFIG_FILE", "config"), config_section),
+        (
+            config_file("AWS_SHARED_CREDENTIALS_FILE", "credentials"),
+            profile,
+        ),
+    ];
+
+    let mut config = vec![];
+    for (file, section) in files {
+        let Some(file) = file.filter(|f| f.is_file()) else {
+            continue;
+        };
+        let content = std::fs::read_to_string(&file)?;
+        config.extend(
+            ini_section(&content, &section)
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.parse().ok()?, value))),
+        );
+    }
+    Ok(config)
+}
+
+fn config_file(var: &str, name: &str) -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(var) {
+        return Some(PathBuf::from(path));
+    }
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()?;
+    Some(PathBuf::from(home).join(".aws").join(name))
+}
+
+/// The `key = value` pairs of a `[section]` of an ini file
+fn ini_section(content: &str, section: &str) -> Vec<(String, String)> {
+    let mut current = None;
+    let mut pairs = vec![];
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim());
+            continue;
+        }
+        if current != Some(section) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            pairs.push((key.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# shared settings
+[default]
+region = eu-west-1
+output = json
+
+[dev]
+region = us-west-2
+
+[profile dev]
+; the region of the dev profile
+Region=eu-central-1
+endpoint = http://localhost:9000
+";
+
+    const CREDENTIALS: &str = "\
+[default]
+aws_access_key_id = default-key
+aws_secret_access_key = default-secret
+
+[dev]
+aws_access_key_id = dev-key
+aws_secret_access_key = dev-secret
+";
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn ini_section_should_read_the_pairs_of_a_section_only() {
+        let default = [("region", "eu-west-1"), ("output", "json")];
+        assert_eq!(ini_section(CONFIG, "default"), pairs(&default));
+        // the config file prefixes named profiles, `[dev]` isn't the dev profile there
+        let dev = [
+            ("region", "eu-central-1"),
+            ("endpoint", "http://localhost:9000"),
+        ];
+        assert_eq!(ini_section(CONFIG, "profile dev"), pairs(&dev));
+        assert_eq!(
+            ini_section(CONFIG, "dev"),
+            pairs(&[("region", "us-west-2")])
+        );
+        assert!(ini_section(CONFIG, "prod").is_empty());
+    }
+
+    /// The environment is shared by the tests, so every case depending on it is run here
+    #[test]
+    fn s3_builder_should_read_the_profile_then_the_environment() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (config, credentials) = (dir.path().join("config"), dir.path().join("credentials"));
+        std::fs::write(&config, CONFIG)?;
+        std::fs::write(&credentials, CREDENTIALS)?;
+        for var in std::env::vars()
+            .map(|(k, _)| k)
+            .filter(|k| k.starts_with("AWS_"))
+        {
+            std::env::remove_var(var);
+        }
+        std::env::set_var("AWS_CONFIG_FILE", &config);
+        std::env::set_var("AWS_SHARED_CREDENTIALS_FILE", &credentials);
+        let settings = || -> anyhow::Result<Vec<Option<String>>> {
+            let builder = s3_builder()?;
+            Ok([
+                AmazonS3ConfigKey::Region,
+                AmazonS3ConfigKey::AccessKeyId,
+                AmazonS3ConfigKey::Endpoint,
+            ]
+            .iter()
+            .map(|key| builder.get_config_value(key))
+            .collect())
+        };
+        let some = |v: &str| Some(v.to_string());
+
+        let default = settings();
+        std::env::set_var("AWS_PROFILE", "dev");
+        let dev = settings();
+        std::env::set_var("AWS_REGION", "ap-south-1");
+        let overridden = settings();
+        std::env::set_var("AWS_PROFILE", "prod");
+        let missing = settings();
+        for var in [
+            "AWS_CONFIG_FILE",
+            "AWS_SHARED_CREDENTIALS_FILE",
+            "AWS_PROFILE",
+            "AWS_REGION",
+        ] {
+            std::env::remove_var(var);
+        }
+
+        assert_eq!(default?, [some("eu-west-1"), some("default-key"), None]);
+        assert_eq!(
+            dev?,
+            [
+                some("eu-central-1"),
+                some("dev-key"),
+                some("http://localhost:9000")
+            ]
+        );
+        assert_eq!(
+            overridden?,
+            [
+                some("ap-south-1"),
+                some("dev-key"),
+                some("http://localhost:9000")
+            ]
+        );
+        assert_eq!(missing?, [some("ap-south-1"), None, None]);
+        Ok(())
+    }
+
+    #[test]
+    fn remote_store_should_reject_urls_without_a_bucket_or_host() {
+        for url in ["s3:///key", "https://", "ftp://host/file"] {
+            assert!(remote_store(url).is_err(), "{}", url);
+        }
+        let e = remote_store("s3:///key").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid s3 url s3:///key, expected s3://bucket/key"
+        );
+    }
+}
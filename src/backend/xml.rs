@@ -0,0 +1,415 @@
+use crate::cli::connect::{FileOpts, XmlOpts};
+use anyhow::anyhow;
+use datafusion::arrow::array::{Array, ArrayRef, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 8192;
+
+/// The columns of the rows of an XML file, in the order they are registered
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlColumns(pub Vec<XmlColumn>);
+
+/// A column taking the text of the element or the attribute at `path` in every row
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlColumn {
+    pub name: String,
+    pub path: String,
+}
+
+/// Parse columns given as `name=path,...`, paths are relative to the row element like
+/// `title`, `author/name`, `@id` or `author/@id`, `.` being the text of the row itself.
+/// Namespace prefixes are dropped since elements are matched by their local name
+pub fn parse_xml_columns(spec: &str) -> anyhow::Result<XmlColumns> {
+    let columns = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(|column| {
+            let Some((name, path)) = column.split_once('=') else {
+                return Err(anyhow!("invalid column {}, expected name=path", column));
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(anyhow!("invalid column {}, the name is empty", column));
+            }
+            Ok(XmlColumn {
+                name: name.to_string(),
+                path: normalize_path(path.trim()),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if columns.is_empty() {
+        return Err(anyhow!("invalid columns {}, no columns", spec));
+    }
+    Ok(XmlColumns(columns))
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.trim_start_matches("./").trim_end_matches("/text()");
+    if matches!(path, "" | "." | "text()") {
+        return ".".to_string();
+    }
+    path.split('/')
+        .map(|step| match step.strip_prefix('@') {
+            Some(attr) => format!("@{}", local_name(attr)),
+            None => local_name(step).to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Read the elements named like the row tag as rows, or the children of the root element
+/// without one. Columns are the attributes and child elements of the rows unless mapped,
+/// as strings unless `schema` gives them another type. Elements are read as the text they
+/// contain, missing ones and empty values of typed columns are null
+pub fn read_xml(
+    file_opts: &FileOpts,
+    xml_opts: &XmlOpts,
+    schema: Option<&SchemaRef>,
+) -> anyhow::Result<(SchemaRef, Vec<RecordBatch>)> {
+    let file = File::open(&file_opts.filename)?;
+    let reader = BufReader::new(file_opts.compression.convert_read(file)?);
+    let mut reader = Reader::from_reader(reader);
+    let mut rows = XmlRows::new(xml_opts);
+    let mut buf = vec![];
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => rows.open(&e)?,
+            Event::Empty(e) => {
+                rows.open(&e)?;
+                rows.close();
+            }
+            Event::End(_) => rows.close(),
+            Event::Text(e) => rows.text(&e.unescape()?),
+            Event::CData(e) => rows.text(&String::from_utf8_lossy(&e)),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if rows.rows.is_empty() {
+        return Err(anyhow!(
+            "no {} elements found in {}",
+            rows.row_tag.as_deref().unwrap_or("row"),
+            file_opts.filename
+        ));
+    }
+    let columns = match &xml_opts.xpath {
+        Some(columns) => columns.0.clone(),
+        None => rows.columns(),
+    };
+    if columns.is_empty() {
+        return Err(anyhow!(
+            "the rows of {} have no attributes or child elements, map them with --xpath",
+            file_opts.filename
+        ));
+    }
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let data_type = schema
+                .and_then(|s| s.field_with_name(&column.name).ok())
+                .map_or(DataType::Utf8, |f| f.data_type().clone());
+            Field::new(&column.name, data_type, true)
+        })
+        .collect::<Vec<_>>();
+    let xml_schema = Arc::new(Schema::new(fields));
+    let batches = rows
+        .rows
+        .chunks(BATCH_SIZE)
+        .enumerate()
+        .map(|(i, chunk)| to_batch(&xml_schema, &columns, chunk, i * BATCH_SIZE))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((xml_schema, batches))
+}
+
+/// The rows read so far, with the values of the paths that are columns
+struct XmlRows {
+    row_tag: Option<String>,
+    paths: Option<HashSet<String>>,
+    depth: usize,
+    /// Path and text of the open elements of the row being read, empty outside rows
+    open: Vec<(String, String)>,
+    values: HashMap<String, String>,
+    rows: Vec<HashMap<String, String>>,
+    /// Attributes and children of the rows in the order they are found, without a mapping
+    found: Vec<String>,
+}
+
+impl XmlRows {
+    fn new(xml_opts: &XmlOpts) -> Self {
+        Self {
+            row_tag: xml_opts
+                .row_tag
+                .as_deref()
+                .map(|t| local_name(t).to_string()),
+            paths: xml_opts
+                .xpath
+                .as_ref()
+                .map(|columns| columns.0.iter().map(|c| c.path.clone()).collect()),
+            depth: 0,
+            open: vec![],
+            values: HashMap::new(),
+            rows: vec![],
+            found: vec![],
+        }
+    }
+
+    fn open(&mut self, e: &BytesStart) -> anyhow::Result<()> {
+        let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+        self.depth += 1;
+        let path = match self.open.last() {
+            Some((parent, _)) if parent == "." => name,
+            Some((parent, _)) => format!("{}/{}", parent, name),
+            None => {
+                if self.row_tag.is_none() && self.depth == 2 {
+                    self.row_tag = Some(name.clone());
+                }
+                if self.row_tag.as_ref() != Some(&name) {
+                    return Ok(());
+                }
+                ".".to_string()
+            }
+        };
+        for attr in e.attributes() {
+            let attr = attr?;
+            if attr.key.as_namespace_binding().is_some() {
+                continue;
+            }
+            let attr_name = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+            let attr_path = match path.as_str() {
+                "." => format!("@{}", attr_name),
+                path => format!("{}/@{}", path, attr_name),
+            };
+            self.set(attr_path, attr.unescape_value()?.to_string());
+        }
+        self.open.push((path, String::new()));
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        let Some((path, text)) = self.open.pop() else {
+            return;
+        };
+        // elements are read as the text of all their descendants
+        if let Some((_, parent)) = self.open.last_mut() {
+            parent.push_str(&text);
+        }
+        self.set(path.clone(), text.trim().to_string());
+        if path == "." {
+            self.rows.push(std::mem::take(&mut self.values));
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        // whitespace between elements is indentation
+        if text.trim().is_empty() {
+            return;
+        }
+        if let Some((_, open)) = self.open.last_mut() {
+            open.push_str(text);
+        }
+    }
+
+    /// Keep the value of a path that is a column, the first one if an element is repeated
+    fn set(&mut self, path: String, value: String) {
+        let is_column = match &self.paths {
+            Some(paths) => paths.contains(&path),
+            None => path != "." && !path.contains('/'),
+        };
+        if !is_column || self.values.contains_key(&path) {
+            return;
+        }
+        if self.paths.is_none() && !self.found.contains(&path) {
+            self.found.push(path.clone());
+        }
+        self.values.insert(path, value);
+    }
+
+    /// Attributes are named without `@` unless a child element has the same name
+    fn columns(&self) -> Vec<XmlColumn> {
+        self.found
+            .iter()
+            .map(|path| {
+                let name = match path.strip_prefix('@') {
+                    Some(attr) if !self.found.iter().any(|p| p == attr) => attr,
+                    _ => path,
+                };
+                XmlColumn {
+                    name: name.to_string(),
+                    path: path.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn to_batch(
+    schema: &SchemaRef,
+    columns: &[XmlColumn],
+    rows: &[HashMap<String, String>],
+    offset: usize,
+) -> anyhow::Result<RecordBatch> {
+    let arrays = schema
+        .fields()
+        .iter()
+        .zip(columns)
+        .map(|(field, column)| {
+            let is_string = field.data_type() == &DataType::Utf8;
+            let values = rows
+                .iter()
+                .map(|row| row.get(&column.path).map(String::as_str))
+                .map(|v| v.filter(|v| is_string || !v.is_empty()))
+                .collect::<StringArray>();
+            let array = cast(&values, field.data_type())?;
+            // values that can't be cast are made null, the first one is reported instead
+            let invalid = (0..values.len()).find(|r| values.is_valid(*r) && array.is_null(*r));
+            if let Some(row) = invalid {
+                return Err(anyhow!(
+                    "invalid {} value {} of column {} in row {}",
+                    field.data_type(),
+                    values.value(row),
+                    field.name(),
+                    offset + row + 1
+                ));
+            }
+            Ok(array)
+        })
+        .collect::<anyhow::Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+    const BOOKS: &str = r#"<?xml version="1.0"?>
+<catalog xmlns:b="urn:books">
+  <b:book id="1">
+    <title>Dune</title>
+    <author><name>Herbert</name></author>
+    <year>1965</year>
+  </b:book>
+  <b:book id="2">
+    <title><![CDATA[Foundation & Empire]]></title>
+    <author><name>Asimov</name></author>
+    <year></year>
+  </b:book>
+</catalog>
+"#;
+
+    fn file(dir: &tempfile::TempDir, content: &str) -> FileOpts {
+        let path = dir.path().join("books.xml");
+        std::fs::write(&path, content).unwrap();
+        FileOpts::new(
+            path.to_string_lossy().to_string(),
+            "xml".to_string(),
+            FileCompressionType::UNCOMPRESSED,
+        )
+    }
+
+    fn format(batches: &[RecordBatch]) -> String {
+        pretty_format_batches(batches).unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_xml_columns_should_normalize_paths() {
+        let columns = parse_xml_columns("id=@b:id, name=./b:author/name/text(), text=.").unwrap();
+        let paths = columns
+            .0
+            .iter()
+            .map(|c| c.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["@id", "author/name", "."]);
+        assert!(parse_xml_columns("id").is_err());
+        assert!(parse_xml_columns("=@id").is_err());
+        assert!(parse_xml_columns(" , ").is_err());
+    }
+
+    #[test]
+    fn read_xml_should_read_the_children_of_the_root_element() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, BOOKS);
+        let (schema, batches) = read_xml(&file_opts, &XmlOpts::default(), None).unwrap();
+        let names = schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "title", "author", "year"]);
+        let expected = [
+            "+----+---------------------+---------+------+",
+            "| id | title               | author  | year |",
+            "+----+---------------------+---------+------+",
+            "| 1  | Dune                | Herbert | 1965 |",
+            "| 2  | Foundation & Empire | Asimov  |      |",
+            "+----+---------------------+---------+------+",
+        ];
+        assert_eq!(format(&batches), expected.join("\n"));
+    }
+
+    #[test]
+    fn read_xml_should_map_nested_paths_of_the_row_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, BOOKS);
+        let xml_opts = XmlOpts {
+            row_tag: Some("b:book".to_string()),
+            xpath: Some(parse_xml_columns("id=@id,author=author/name,year=year").unwrap()),
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("year", DataType::Int32, true),
+        ]));
+        let (xml_schema, batches) = read_xml(&file_opts, &xml_opts, Some(&schema)).unwrap();
+        assert_eq!(
+            xml_schema.field_with_name("year").unwrap().data_type(),
+            &DataType::Int32
+        );
+        let expected = [
+            "+----+---------+------+",
+            "| id | author  | year |",
+            "+----+---------+------+",
+            "| 1  | Herbert | 1965 |",
+            "| 2  | Asimov  |      |",
+            "+----+---------+------+",
+        ];
+        assert_eq!(format(&batches), expected.join("\n"));
+    }
+
+    #[test]
+    fn read_xml_should_report_invalid_values_and_missing_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_opts = file(&dir, BOOKS);
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "title",
+            DataType::Int32,
+            true,
+        )]));
+        let err = read_xml(&file_opts, &XmlOpts::default(), Some(&schema)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid Int32 value Dune of column title in row 1"
+        );
+        let xml_opts = XmlOpts {
+            row_tag: Some("record".to_string()),
+            xpath: None,
+        };
+        let err = read_xml(&file_opts, &xml_opts, None).unwrap_err();
+        assert!(err.to_string().starts_with("no record elements found in "));
+    }
+}
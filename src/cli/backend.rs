@@ -0,0 +1,37 @@
+use crate::backend::BackendKind;
+use crate::cli::ReplCommand;
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct BackendOpts {
+    #[arg(value_enum, help = "Backend name")]
+    pub name: BackendKind,
+}
+
+impl BackendOpts {
+    pub fn new(name: BackendKind) -> Self {
+        Self { name }
+    }
+}
+
+pub fn backend(
+    args: ArgMatches,
+    context: &mut ReplContext,
+) -> reedline_repl_rs::Result<Option<String>> {
+    let name = args
+        .get_one::<BackendKind>("name")
+        .expect("backend name not found")
+        .to_owned();
+
+    let cmd = ReplCommand::Backend(BackendOpts::new(name));
+    let (msg, rx) = ReplMsg::new(cmd);
+    Ok(context.send(msg, rx))
+}
+
+impl CmdExecutor for BackendOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        backend.switch(self.name).await?;
+        Ok(format!("switched to backend: {}", self.name))
+    }
+}
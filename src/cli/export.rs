@@ -0,0 +1,62 @@
+use crate::cli::connect::{target_parser, DataSetConn};
+use crate::cli::ReplCommand;
+use crate::{Backend, CmdExecutor, ReplContext, ReplMsg};
+use clap::{ArgMatches, Parser};
+
+#[derive(Debug, Parser)]
+pub struct ExportOpts {
+    #[arg(help = "Dataset name, or SQL query with --sql")]
+    pub source: String,
+    #[arg(
+        value_parser = target_parser,
+        help = "Output file, parquet, arrow, csv or json chosen by the extension, optionally compressed"
+    )]
+    pub target: DataSetConn,
+    #[arg(long = "sql", help = "Export the result of the source as a SQL query")]
+    pub query: bool,
+}
+
+impl ExportOpts {
+    pub fn new(source: String, target: DataSetConn, query: bool) -> Self {
+        Self {
+            source,
+            target,
+            query,
+        }
+    }
+
+    /// The query of the rows to export, every row of the dataset unless the source is a query
+    pub fn sql(&self) -> String {
+        match self.query {
+            true => self.source.clone(),
+            false => format!("SELECT * FROM {}", self.source),
+        }
+    }
+}
+
+pub fn export(
+    args: ArgMatches,
+    context: &mut ReplContext,
+) -> reedline_repl_rs::Result<Option<String>> {
+    let source = args
+        .get_one::<String>("source")
+        .expect("source not found")
+        .to_owned();
+    let target = args
+        .get_one::<DataSetConn>("target")
+        .expect("target not found")
+        .to_owned();
+    let query = args.get_flag("sql");
+
+    let cmd = ReplCommand::Export(ExportOpts::new(source, target, query));
+    let (msg, rx) = ReplMsg::new(cmd);
+    Ok(context.send(msg, rx))
+}
+
+impl CmdExecutor for ExportOpts {
+    async fn execute<T: Backend>(self, backend: &mut T) -> anyhow::Result<String> {
+        let source = self.source.clone();
+        let rows = backend.export(self).await?;
+        Ok(format!("exported {} rows from {}", rows, source))
+    }
+}
@@ -1,13 +1,15 @@
+pub(crate) mod backend;
 pub(crate) mod connect;
 pub(crate) mod describe;
+pub(crate) mod export;
 pub(crate) mod head;
 pub(crate) mod list;
 pub(crate) mod schema;
 pub(crate) mod sql;
 
 pub use crate::cli::{
-    connect::ConnectOpts, describe::DescribeOpts, head::HeadOpts, list::ListOpts,
-    schema::SchemaOpts, sql::SqlOpts,
+    backend::BackendOpts, connect::ConnectOpts, describe::DescribeOpts, export::ExportOpts,
+    head::HeadOpts, list::ListOpts, schema::SchemaOpts, sql::SqlOpts,
 };
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
@@ -19,7 +21,7 @@ pub enum ReplCommand {
         name = "connect",
         about = "Connect to a dataset and register it to DF(data-forge-rs)"
     )]
-    Connect(ConnectOpts),
+    Connect(Box<ConnectOpts>),
     #[command(name = "list", about = "List all registered datasets")]
     List(ListOpts),
     #[command(name = "schema", about = "Get the schema of a dataset")]
@@ -30,4 +32,14 @@ pub enum ReplCommand {
     Head(HeadOpts),
     #[command(name = "sql", about = "Run a SQL query on a dataset")]
     Sql(SqlOpts),
+    #[command(
+        name = "backend",
+        about = "Switch the backend engine, connected datasets are registered again"
+    )]
+    Backend(BackendOpts),
+    #[command(
+        name = "export",
+        about = "Export a dataset or the result of a SQL query to a file"
+    )]
+    Export(ExportOpts),
 }
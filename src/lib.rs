@@ -1,14 +1,16 @@
 pub mod backend;
 pub mod cli;
 
-use crate::backend::df::DataFusionBackend;
+use crate::backend::{BackendKind, ReplBackend};
 use crate::cli::*;
 use crossbeam_channel as mpsc;
 use enum_dispatch::enum_dispatch;
 use reedline_repl_rs::CallBackMap;
+use std::path::PathBuf;
 use std::thread;
 pub struct ReplContext {
     pub tx: mpsc::Sender<ReplMsg>,
+    scratch_dir: PathBuf,
 }
 
 pub struct ReplMsg {
@@ -40,16 +42,39 @@ pub(crate) trait Backend {
     async fn describe(&self, opts: DescribeOpts) -> anyhow::Result<impl ReplDisplay>;
     async fn head(&self, opts: HeadOpts) -> anyhow::Result<impl ReplDisplay>;
     async fn sql(&self, opts: SqlOpts) -> anyhow::Result<impl ReplDisplay>;
+    async fn export(&self, opts: ExportOpts) -> anyhow::Result<usize>;
+    async fn switch(&mut self, kind: BackendKind) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("backend can't be switched to {}", kind))
+    }
+    /// Where standard input and archive members are written to be connected
+    fn scratch_dir(&self) -> PathBuf {
+        backend::scratch_dir()
+    }
 }
 
 pub(crate) trait ReplDisplay {
     async fn display(self) -> anyhow::Result<String>;
 }
 
+impl Default for ReplContext {
+    fn default() -> Self {
+        Self::new(BackendKind::default())
+    }
+}
+
+impl Drop for ReplContext {
+    fn drop(&mut self) {
+        // the datasets can't be used after the session, nor the files written to connect them,
+        // those of other sessions of the process are kept
+        let _ = std::fs::remove_dir_all(&self.scratch_dir);
+    }
+}
+
 impl ReplContext {
-    pub fn new() -> Self {
+    pub fn new(kind: BackendKind) -> Self {
         let (tx, rx) = mpsc::unbounded::<ReplMsg>();
-        let mut backend = DataFusionBackend::new();
+        let mut backend = ReplBackend::new(kind);
+        let scratch_dir = backend.scratch_dir().to_path_buf();
         let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
         thread::spawn(move || {
             while let Ok(msg) = rx.recv() {
@@ -62,7 +87,7 @@ impl ReplContext {
                 }
             }
         });
-        Self { tx }
+        Self { tx, scratch_dir }
     }
 
     pub fn send(&self, cmd: ReplMsg, rx: oneshot::Receiver<String>) -> Option<String> {
@@ -91,5 +116,36 @@ pub fn get_callbacks() -> CallBackMap<ReplContext, reedline_repl_rs::Error> {
     map.insert("describe".to_string(), cli::describe::describe);
     map.insert("head".to_string(), cli::head::head);
     map.insert("sql".to_string(), cli::sql::sql);
+    map.insert("backend".to_string(), cli::backend::backend);
+    map.insert("export".to_string(), cli::export::export);
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn connect_inline(context: &ReplContext, data: &str, name: &str) -> Option<String> {
+        let args = ["connect", "--inline", data, "-n", name];
+        let opts = ConnectOpts::try_parse_from(args).expect("valid connect options");
+        let (msg, rx) = ReplMsg::new(ReplCommand::Connect(Box::new(opts)));
+        context.send(msg, rx)
+    }
+
+    #[test]
+    fn dropping_a_context_should_remove_only_its_scratch_dir() {
+        let (a, b) = (ReplContext::default(), ReplContext::default());
+        assert_ne!(a.scratch_dir, b.scratch_dir);
+        assert!(connect_inline(&a, "a\\n1", "a").is_some());
+        assert!(connect_inline(&b, "b\\n2", "b").is_some());
+        assert!(a.scratch_dir.join("stdin").is_dir());
+        assert!(b.scratch_dir.join("stdin").is_dir());
+
+        let a_dir = a.scratch_dir.clone();
+        drop(a);
+        assert!(!a_dir.exists());
+        assert!(b.scratch_dir.join("stdin").is_dir());
+        assert!(connect_inline(&b, "c\\n3", "c").is_some());
+    }
+}
@@ -1,10 +1,35 @@
+use clap::{CommandFactory, Parser};
+use data_forge_rs::backend::BackendKind;
 use data_forge_rs::cli::ReplCommand;
 use data_forge_rs::ReplContext;
 use reedline_repl_rs::Repl;
-use std::env;
+use std::{env, process};
+
+#[derive(Debug, Parser)]
+#[command(about = "Data Forge, your data exploration companion")]
+struct Args {
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Backend engine to start with"
+    )]
+    backend: BackendKind,
+    #[arg(
+        short = 'c',
+        long = "command",
+        help = "Run a command and exit instead of starting the REPL, can be repeated, \
+                e.g. -c 'connect - -n x' -c 'sql \"select * from x\"'"
+    )]
+    commands: Vec<String>,
+}
 
 fn main() -> reedline_repl_rs::Result<()> {
-    let ctx = ReplContext::new();
+    let args = Args::parse();
+    let ctx = ReplContext::new(args.backend);
+    if !args.commands.is_empty() {
+        return run_commands(ctx, &args.commands);
+    }
     let callbacks = data_forge_rs::get_callbacks();
     let history = env::current_dir()
         .expect("Fail to get current dir")
@@ -16,3 +41,26 @@ fn main() -> reedline_repl_rs::Result<()> {
 
     repl.run()
 }
+
+/// Run the commands one after the other through the REPL callbacks, standard input is left
+/// alone so data can be piped to `connect -`
+fn run_commands(mut ctx: ReplContext, commands: &[String]) -> reedline_repl_rs::Result<()> {
+    let callbacks = data_forge_rs::get_callbacks();
+    for command in commands {
+        let Some(words) = shlex::split(command) else {
+            eprintln!("invalid command, unbalanced quotes: {}", command);
+            process::exit(1);
+        };
+        let matches = ReplCommand::command()
+            .no_binary_name(true)
+            .try_get_matches_from(words)
+            .unwrap_or_else(|e| e.exit());
+        let Some((name, args)) = matches.subcommand() else {
+            continue;
+        };
+        if let Some(output) = callbacks[name](args.clone(), &mut ctx)? {
+            println!("{}", output);
+        }
+    }
+    Ok(())
+}